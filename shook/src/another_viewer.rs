@@ -5,9 +5,12 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::ser::SerializeSeq;
 use shook_config::{Ephemeral, Secret};
-use shook_core::{prelude::*, IterExt as _, PersistFromConfig};
+use shook_core::{
+    callable::{Cooldown, Throttle},
+    prelude::*,
+    IterExt as _, PersistFromConfig,
+};
 use shook_helix::EmoteMap;
-use tokio::{sync::Mutex, time::Instant};
 
 #[derive(Clone, Debug, Default)]
 struct Patterns {
@@ -44,11 +47,13 @@ impl serde::Serialize for Patterns {
 
 impl PersistFromConfig for Patterns {
     type ConfigPath = crate::config::AnotherViewer;
+    const KEY: &'static str = "filter_patterns";
 }
 
 pub struct AnotherViewer {
-    last: Mutex<Instant>,
-    emote_map: EmoteMap,
+    throttle: Throttle,
+    // this follows the emote map in the state, so a new one is used right away
+    emote_map: Watch<EmoteMap>,
     client: reqwest::Client,
     bearer_token: Ephemeral,
     endpoint: Secret,
@@ -56,18 +61,24 @@ pub struct AnotherViewer {
 }
 
 impl AnotherViewer {
+    // these are the commands `bind` binds, so they can be listed without connecting to anything
+    pub fn declared() -> Vec<Command> {
+        vec![Self::speak_command()]
+    }
+
     pub async fn bind(state: GlobalState) -> anyhow::Result<SharedCallable> {
         let crate::config::AnotherViewer {
             endpoint,
             bearer_token,
             ..
-        } = state.get_owned().await;
+        } = state.get_owned()?;
 
         let patterns = Patterns::load_from_file(&state).await?;
-        let emote_map = state.get_owned().await;
+        let emote_map = state.subscribe();
 
         let this = Self {
-            last: Mutex::new(Instant::now()),
+            // this waits a full cooldown after starting before talking on its own
+            throttle: Throttle::started_at(state.clock().now()),
             client: reqwest::Client::new(),
             emote_map,
             bearer_token,
@@ -77,18 +88,23 @@ impl AnotherViewer {
 
         Ok(Binding::create(state, this)
             .await
-            .bind(Self::speak)
+            .bind_cmd(Self::speak_command(), Self::speak)
             .listen(Self::listen)
             .into_callable())
     }
 
+    #[command(
+        name = "!speak",
+        usage = "<context?>",
+        help = "says something, starting from the context if one is given"
+    )]
     async fn speak(self: Arc<Self>, msg: Message) -> impl Render {
         let ctx = msg.args().get("context");
         self.generate(ctx).await.map(Response::reply)
     }
 
     async fn listen(self: Arc<Self>, msg: Message) -> impl Render {
-        if msg.data().starts_with('!') {
+        if msg.is_command() {
             return None;
         }
 
@@ -103,7 +119,7 @@ impl AnotherViewer {
             return Some(msg.boxed());
         }
 
-        if !self.check_timeout().await {
+        if !self.check_timeout(&msg).await {
             return None;
         }
         self.generate(None).await.map(|r| r.boxed())
@@ -117,21 +133,25 @@ impl AnotherViewer {
     }
 
     async fn try_kappa<'a>(&self, ctx: &'a [&'a str]) -> Option<impl Render> {
+        let emote_map = self.emote_map.current()?;
         let kappa = ctx
             .iter()
-            .filter(|c| self.emote_map.has(c))
+            .filter(|c| emote_map.has(c))
             .choose(&fastrand::Rng::new())?;
         self.generate(Some(kappa)).await
     }
 
-    async fn check_timeout(&self) -> bool {
-        const COOLDOWN: Duration = Duration::from_secs(60);
-        let mut last = self.last.lock().await;
-        if last.elapsed() >= COOLDOWN {
-            *last = Instant::now();
-            return true;
-        }
-        false
+    async fn check_timeout(&self, msg: &Message) -> bool {
+        const COOLDOWN: u64 = 60;
+        let now = msg.state().clock().now();
+        self.throttle
+            .try_acquire(
+                &Cooldown::global(COOLDOWN),
+                now,
+                msg.sender_name(),
+                msg.source(),
+            )
+            .is_ok()
     }
 
     async fn train(&self, data: &str) -> Option<()> {
@@ -238,3 +258,115 @@ impl AnotherViewer {
         ctx.iter().copied().any(match_name)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use shook_core::{
+        prelude::State,
+        testing::{Canned, Mock as _, MockMessage, StandIn, TestBinding},
+    };
+
+    use super::*;
+
+    async fn binding(dir: &tempfile::TempDir, server: &StandIn) -> TestBinding {
+        let path = dir.path().join("filter_patterns.json");
+        std::fs::write(&path, r#"["lol+"]"#).unwrap();
+
+        let mut state = State::default();
+        state.insert(crate::config::AnotherViewer {
+            endpoint: Secret::key("SHAKEN_REVERSE_PROXY").with_value(&server.url()),
+            bearer_token: Ephemeral::key("SHAKEN_BRAIN_GENERATE_TOKEN").with_value("token"),
+            filter_patterns_path: path,
+        });
+        AnotherViewer::bind.mock_with_state(state).await
+    }
+
+    fn body(server: &StandIn, path: &str) -> serde_json::Value {
+        let req = server
+            .requests()
+            .into_iter()
+            .find(|req| req.path == path)
+            .unwrap();
+        serde_json::from_str(&req.body).unwrap()
+    }
+
+    #[tokio::test]
+    #[command(
+        name = "!speak",
+        usage = "<context?>",
+        help = "says something, starting from the context if one is given"
+    )]
+    async fn speak() {
+        let generated = serde_json::json!({ "data": "hello @someone there" });
+        let server = StandIn::builder()
+            .get("/shaken/brain/generate", Canned::json(&generated))
+            .post("/shaken/brain/train", Canned::status(200))
+            .start()
+            .await
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut binding = binding(&dir, &server).await;
+        binding.send_message::<MockMessage>("!speak").await;
+        assert_eq!(
+            binding.get_response(),
+            [Response::Reply(String::from("hello there"))]
+        );
+        assert_eq!(
+            body(&server, "/shaken/brain/generate"),
+            serde_json::json!({ "min": 3, "max": 50, "context": null })
+        );
+    }
+
+    #[tokio::test]
+    async fn mentions_are_trained_and_answered() {
+        let generated = serde_json::json!({ "data": "hi" });
+        let server = StandIn::builder()
+            .get("/shaken/brain/generate", Canned::json(&generated))
+            .post("/shaken/brain/train", Canned::status(200))
+            .start()
+            .await
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut binding = binding(&dir, &server).await.with_sender("bob");
+        binding
+            .send_message::<MockMessage>("shaken what's up lol")
+            .await;
+        assert_eq!(
+            binding.get_response(),
+            [Response::Reply(String::from("hi"))]
+        );
+
+        let train = server
+            .requests()
+            .into_iter()
+            .find(|req| req.path == "/shaken/brain/train")
+            .unwrap();
+        assert_eq!(train.header("authorization"), Some("Bearer token"));
+        assert_eq!(
+            body(&server, "/shaken/brain/train"),
+            serde_json::json!({ "data": "what's up" })
+        );
+        assert_eq!(body(&server, "/shaken/brain/generate")["context"], "bob");
+    }
+
+    #[tokio::test]
+    async fn errors_are_quiet() {
+        let server = StandIn::builder()
+            .get("/shaken/brain/generate", Canned::status(500))
+            .post("/shaken/brain/train", Canned::status(500))
+            .start()
+            .await
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut binding = binding(&dir, &server).await;
+        binding.send_message::<MockMessage>("!speak").await;
+        assert!(binding.get_response().is_empty());
+
+        binding.send_message::<MockMessage>("hello shaken").await;
+        assert!(binding.get_response().is_empty());
+        assert_eq!(server.requests().len(), 3);
+    }
+}
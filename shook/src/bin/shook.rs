@@ -1,35 +1,129 @@
-use std::sync::Arc;
+use std::{any::Any, future::Future, path::Path, sync::Arc};
 
 use shook::config::Config;
-use shook_core::prelude::{GlobalState, State, StreamerName};
-use shook_helix::{EmoteMap, HelixClient, OAuth};
+use shook_core::{
+    help::Registry,
+    prelude::{
+        Binding, GlobalState, IntoCallable as _, SharedCallable, SharedRegistry, State,
+        StreamerName,
+    },
+    schedule::SharedLiveCheck,
+};
+use shook_helix::{EmoteMap, HelixClient, LiveStream, OAuth};
 
 use persist::{tokio::PersistExt as _, yaml::Yaml};
 
 async fn load_configurations(state: &mut State) -> anyhow::Result<()> {
     // TODO from env
 
-    let config = Config::load_from_file::<Yaml>(std::env::var("SHOOK_CONFIG_PATH")?).await?;
+    let config = Config::load(std::env::var("SHOOK_CONFIG_PATH")?).await?;
 
     state.insert(config.twitch);
-    state.insert(config.discord);
     state.insert(config.helix);
-    state.insert(config.spotify);
-    state.insert(config.another_viewer);
-    state.insert(config.builtin);
-    state.insert(config.youtube);
+    insert_section(state, config.discord);
+    insert_section(state, config.spotify);
+    insert_section(state, config.another_viewer);
+    insert_section(state, config.builtin);
+    insert_section(state, config.youtube);
+    state.insert(config.crates);
     state.insert(config.user_defined);
     state.insert(config.registry);
+    state.insert(config.catalog);
+    state.insert(config.dispatch);
+    state.insert(config.locale);
 
     Ok(())
 }
 
+fn insert_section<T>(state: &mut State, section: Option<T>)
+where
+    T: Any + Send + Sync + 'static,
+{
+    if let Some(section) = section {
+        state.insert(section)
+    }
+}
+
+// a module without its config section is left out
+async fn bind_if(
+    state: &GlobalState,
+    name: &str,
+    configured: bool,
+    bind: impl Future<Output = anyhow::Result<SharedCallable>> + Send,
+) -> anyhow::Result<SharedCallable> {
+    if !configured {
+        log::warn!("{name} is not configured, leaving it out");
+        return Ok(Binding::create(state.clone(), ()).await.into_callable());
+    }
+    bind.await
+}
+
+// the commands declared with `#[command]` don't need a registry entry
 async fn load_registry(state: &mut State) -> anyhow::Result<()> {
-    let path = state.get_config_path::<shook::config::Registry>();
-    let registry = shook_core::help::Registry::load_from_file::<Yaml>(&path)
-        .await
-        .map(Arc::new)?;
-    state.insert(registry);
+    let path = state.get_config_path::<shook::config::Registry>()?;
+    let registry = if path.exists() {
+        Registry::load_from_file::<Yaml>(&path).await?
+    } else {
+        log::warn!("cannot find {}, using the declared commands", path.display());
+        Registry::default()
+    };
+    registry.validate()?;
+    state.insert(Arc::new(registry));
+    Ok(())
+}
+
+// everything wrong with the bindings is logged at once, only conflicting names stop the bot.
+// afterwards `!help` uses what was bound rather than the registry file
+fn check_bindings(state: &GlobalState, callables: &[SharedCallable]) -> anyhow::Result<()> {
+    let registry = state.get::<SharedRegistry>()?;
+    let report = registry.check_bindings(callables);
+    anyhow::ensure!(!report.has_conflicts(), "invalid bindings:\n{report}");
+    if !report.is_empty() {
+        log::warn!("the registry does not match the bindings:\n{report}")
+    }
+
+    state.insert(Arc::new(Registry::from_bindings(callables)));
+    Ok(())
+}
+
+// this only needs the registry and the user defined commands, so nothing is connected to
+async fn export_commands(state: &GlobalState, dir: impl AsRef<Path>) -> anyhow::Result<()> {
+    let registry = state.get::<SharedRegistry>()?;
+    let mut registry = shook::export::merge_declared(&registry, &shook::declared_commands());
+    for desc in shook::UserDefined::descriptions(state).await? {
+        registry.add(shook::export::USER_DEFINED, desc)
+    }
+
+    let markdown = shook::export::markdown(&registry);
+    let messages = shook::export::discord_messages(&markdown).len();
+    if messages > 1 {
+        log::warn!("commands.md is too long for one discord message, it needs {messages}")
+    }
+
+    let dir = dir.as_ref();
+    tokio::fs::create_dir_all(dir).await?;
+    for (name, data) in [
+        ("commands.md", markdown),
+        ("commands.html", shook::export::html(&registry)),
+    ] {
+        let path = dir.join(name);
+        tokio::fs::write(&path, data).await?;
+        log::info!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+// the built-in english is used for anything the catalog doesn't have
+async fn load_catalog(state: &mut State) -> anyhow::Result<()> {
+    let path = state.get_config_path::<shook::config::Catalog>()?;
+    let catalog = if path.exists() {
+        shook_core::locale::Catalog::load_from_file::<Yaml>(&path).await?
+    } else {
+        log::warn!("cannot find {}, only english is available", path.display());
+        shook_core::locale::Catalog::default()
+    };
+    catalog.validate()?;
+    state.insert(Arc::new(catalog));
     Ok(())
 }
 
@@ -44,8 +138,9 @@ async fn init_twitch(state: &mut State) -> anyhow::Result<()> {
     log::debug!("getting twitch oauth tokens");
     let twitch_oauth = OAuth::create(&twitch.client_id, &twitch.client_secret).await?;
 
-    let twitch_client = HelixClient::new(
-        twitch_oauth.get_client_id(), //
+    let twitch_client = HelixClient::new_with_ep(
+        twitch.endpoint.clone(),
+        twitch_oauth.get_client_id(),
         twitch_oauth.get_bearer_token(),
     );
 
@@ -55,8 +150,11 @@ async fn init_twitch(state: &mut State) -> anyhow::Result<()> {
     let iter = global.iter().map(|c| (&*c.id, &*c.name));
     let emote_map = EmoteMap::default().with_emotes(iter);
 
+    let live: SharedLiveCheck = Arc::new(LiveStream::new(twitch_client.clone(), &streamer.0));
+
     state.insert(twitch_oauth);
     state.insert(twitch_client);
+    state.insert(live);
     state.insert(emote_map);
     state.insert(streamer);
 
@@ -81,19 +179,53 @@ async fn main() -> anyhow::Result<()> {
     log::info!("loading help");
     load_registry(&mut state).await?;
 
+    // `shook export-commands [dir]` writes the command reference and exits
+    if let Some(mode) = std::env::args().nth(1) {
+        anyhow::ensure!(
+            mode == "export-commands",
+            "unknown mode: {mode}, the only one is `export-commands [dir]`"
+        );
+        let dir = std::env::args().nth(2).unwrap_or_else(|| String::from("."));
+        return export_commands(&GlobalState::new(state), dir).await;
+    }
+
+    log::info!("loading message catalog");
+    load_catalog(&mut state).await?;
+
     log::info!("getting twitch clients");
     init_twitch(&mut state).await?;
 
     let state = GlobalState::new(state);
     log::trace!("binding callables");
     let callables = [
-        shook::Builtin::bind(state.clone()).await?,
+        bind_if(
+            &state,
+            "builtin",
+            state.try_get::<shook::config::Builtin>().is_some(),
+            shook::Builtin::bind(state.clone()),
+        )
+        .await?,
         shook::Crates::bind(state.clone()).await?,
         shook::UserDefined::bind(state.clone()).await?,
-        shook::WhatSong::bind(state.clone()).await?,
-        shook::AnotherViewer::bind(state.clone()).await?,
+        bind_if(
+            &state,
+            "what_song",
+            state.try_get::<shook::config::Spotify>().is_some()
+                && state.try_get::<shook::config::Youtube>().is_some(),
+            shook::WhatSong::bind(state.clone()),
+        )
+        .await?,
+        bind_if(
+            &state,
+            "another_viewer",
+            state.try_get::<shook::config::AnotherViewer>().is_some(),
+            shook::AnotherViewer::bind(state.clone()),
+        )
+        .await?,
     ];
 
+    check_bindings(&state, &callables)?;
+
     // TODO these should reconnect automatically
     // TODO these should be configurable
     log::debug!("starting local bot");
@@ -111,7 +243,13 @@ async fn main() -> anyhow::Result<()> {
     log::debug!("starting discord bot");
     let discord = tokio::task::spawn({
         let state = state.clone();
-        shook_twilight::create_bot(state, callables)
+        async move {
+            if state.try_get::<shook_twilight::config::Config>().is_none() {
+                log::warn!("discord is not configured, not starting it");
+                return Ok(());
+            }
+            shook_twilight::create_bot(state, callables).await
+        }
     });
 
     log::debug!("waiting for both bots to finish");
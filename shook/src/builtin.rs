@@ -4,7 +4,7 @@ use std::{
 };
 
 use anyhow::Context;
-use shook_core::{help::Descriptions, prelude::*, FormatTime, USER_AGENT};
+use shook_core::{prelude::*, FormatTime, IterExt, USER_AGENT};
 use shook_local::LocalPort;
 use tokio::time::Instant;
 
@@ -16,14 +16,33 @@ pub struct Builtin {
     uptime: Instant,
     oauth: OAuth,
     gist_id: String,
+    github: String,
 }
 
 impl Builtin {
+    const GITHUB_URL: &'static str = "https://api.github.com";
+
+    // these are the commands `bind` binds, so they can be listed without connecting to anything
+    pub fn declared() -> Vec<Command> {
+        vec![
+            Self::theme_command(),
+            Self::font_command(),
+            Self::uptime_command(),
+            Self::bot_uptime_command(),
+            Self::time_command(),
+            Self::hello_command(),
+            Self::help_command(),
+            Self::version_command(),
+            Self::local_port_command(),
+        ]
+    }
+
     pub async fn bind(state: GlobalState) -> anyhow::Result<SharedCallable> {
         let crate::config::Builtin {
             github_oauth_token,
             settings_gist_id,
-        } = state.get_owned().await;
+            github_endpoint,
+        } = state.get_owned()?;
 
         let this = Self {
             uptime: Instant::now(),
@@ -31,111 +50,99 @@ impl Builtin {
                 token: github_oauth_token.into_string(),
             },
             gist_id: settings_gist_id,
+            github: github_endpoint.unwrap_or_else(|| String::from(Self::GITHUB_URL)),
         };
 
         Ok(Binding::create(state, this)
             .await
-            .bind(Self::theme)
-            .bind(Self::font)
-            .bind(Self::uptime)
-            .bind(Self::bot_uptime)
-            .bind(Self::time)
-            .bind(Self::hello)
-            .bind(Self::help)
-            .bind(Self::version)
-            .bind(Self::local_port)
+            .bind_cmd(Self::theme_command(), Self::theme)
+            .bind_cmd(Self::font_command(), Self::font)
+            .bind_cmd(Self::uptime_command(), Self::uptime)
+            .bind_cmd(Self::bot_uptime_command(), Self::bot_uptime)
+            .bind_cmd(Self::time_command(), Self::time)
+            .bind_cmd(Self::hello_command(), Self::hello)
+            .bind_cmd(Self::help_command(), Self::help)
+            .bind_cmd(Self::version_command(), Self::version)
+            .bind_cmd(Self::local_port_command(), Self::local_port)
             .listen(Self::say_hello)
             .into_callable())
     }
 
+    #[command(
+        name = "!local_port",
+        help = "shows the address of the local control port",
+        permission = "broadcaster"
+    )]
     async fn local_port(self: Arc<Self>, msg: Message) -> impl Render {
-        msg.require_broadcaster()?;
-        Ok(msg.state().get::<LocalPort>().await.to_string())
+        Ok(msg.state().get::<LocalPort>()?.to_string())
     }
 
+    #[command(name = "!version", help = "shows which revision of the bot is running")]
     async fn version(self: Arc<Self>, _: Message) -> impl Render {
-        Simple {
-            twitch: format!(
-                "{} on branch '{}' (built on {})",
-                crate::GIT_REVISION,
-                crate::GIT_BRANCH,
-                crate::BUILD_TIME
-            ),
-            discord: format!(
-                "`{}` on branch `{}` (built on `{}`)",
-                crate::GIT_REVISION,
-                crate::GIT_BRANCH,
-                crate::BUILD_TIME
-            ),
-        }
+        Text::new()
+            .code(crate::GIT_REVISION)
+            .text(" on branch ")
+            .code(crate::GIT_BRANCH)
+            .text(" (built on ")
+            .code(crate::BUILD_TIME)
+            .text(")")
     }
 
+    #[command(
+        name = "!help",
+        usage = "<command?>",
+        help = "lists the commands, or shows how to use one"
+    )]
     async fn help(self: Arc<Self>, msg: Message) -> impl Render {
-        return match msg.args().get("command") {
-            Some(cmd) if !cmd.starts_with('!') => {
-                anyhow::bail!("you must prefix commands with !")
-            }
-
+        let permission = msg.permission();
+        let locale = msg.locale();
+        match msg.args().get("command") {
             // TODO list aliases
             Some(cmd) => {
-                let registry = msg.state().get::<SharedRegistry>().await;
-                match registry.find_command(cmd) {
-                    Some(desc) => Ok(Simple {
-                        twitch: format!(
-                            "{usage} | {desc}",
-                            usage = desc.usage(),
-                            desc = desc.description()
-                        ),
-                        discord: format!(
-                            "`{usage}` | {desc}",
-                            usage = desc.usage(),
-                            desc = desc.description()
-                        ),
-                    }
-                    .boxed()),
-                    None => anyhow::bail!("cannot find '{cmd}'"),
-                }
+                let registry = msg.state().get::<SharedRegistry>()?;
+                let desc = match registry
+                    .find_command_for(&msg.prefixes().canonical(cmd), permission)
+                {
+                    Some(desc) => desc,
+                    None => anyhow::bail!(locale.tr_with("help.not-found", &[("command", &cmd)])),
+                };
+
+                // a catalog can replace the registry's description with `help.<command>`
+                let describe = |name: &str, desc: &str| {
+                    let key = format!("help.{name}");
+                    locale.get(&key).unwrap_or(desc).to_string()
+                };
+
+                // subcommands are listed after their parent, one per line
+                let lines = std::iter::once((
+                    msg.display_command(&desc.usage()),
+                    describe(&desc.command, desc.description()),
+                ))
+                .chain(desc.subcommands_for(permission).map(|sub| {
+                    let name = format!("{} {}", desc.command, sub.command);
+                    let usage = format!("{} {}", desc.command, sub.usage());
+                    (
+                        msg.display_command(&usage),
+                        describe(&name, sub.description()),
+                    )
+                }))
+                .map(|(usage, desc)| Text::new().code(usage).text(" | ").text(desc));
+
+                Ok(Text::new().list(lines).boxed())
             }
             None => {
-                let registry = msg.state().get::<SharedRegistry>().await;
-                let f = format_help_twitch(
-                    &registry
-                        .get_all_descriptions()
-                        .flat_map(Descriptions::command_names)
-                        .collect(),
-                );
-                Ok(Simple {
-                    twitch: f.clone(),
-                    discord: f,
-                }
-                .boxed())
-            }
-        };
-
-        fn format_help_twitch(desc: &BTreeSet<&str>) -> Vec<Response> {
-            const MAX: usize = 10;
-            let (mut left, right) = desc.iter().enumerate().fold(
-                (Response::builder(), String::new()),
-                |(mut left, mut right), (i, c)| {
-                    if i != 0 && i % MAX == 0 {
-                        left = left.say(std::mem::take(&mut right))
-                    }
-                    if !right.is_empty() {
-                        right.push(' ')
-                    }
-                    right.push_str(c);
-                    (left, right)
-                },
-            );
-
-            if !right.trim().is_empty() {
-                left = left.say(right)
+                let registry = msg.state().get::<SharedRegistry>()?;
+                let names = registry
+                    .get_all_descriptions()
+                    .flat_map(|desc| desc.command_names_for(permission))
+                    .map(|name| msg.display_command(name))
+                    .collect::<BTreeSet<_>>();
+                Ok(names.iter().join_with(' ').boxed())
             }
-
-            left.finish()
         }
     }
 
+    #[command(name = "!hello", help = "says hello")]
     async fn hello(self: Arc<Self>, msg: Message) -> impl Render {
         format!("hello, {}!", msg.sender_name())
     }
@@ -148,43 +155,46 @@ impl Builtin {
         Some(format!("hello, {}.", msg.sender_name()))
     }
 
+    #[command(name = "!time", help = "shows the streamer's local time")]
     async fn time(self: Arc<Self>, _: Message) -> impl Render {
         let f = time::format_description::parse("[hour]:[minute]:[second]")?;
         let now = time::OffsetDateTime::now_local()?.format(&f)?;
 
-        Ok(Simple {
-            twitch: format!("current time: {now}"),
-            discord: format!("current time: `{now}`"),
-        })
+        Ok(Text::new().text("current time: ").code(now))
     }
 
+    #[command(name = "!bot_uptime", help = "shows how long the bot has been running")]
     async fn bot_uptime(self: Arc<Self>, _: Message) -> impl Render {
         let uptime = self.uptime.elapsed().as_readable_time();
-        Simple {
-            twitch: format!("I've been running for: {uptime}"),
-            discord: format!("I've been running for: `{uptime}`"),
-        }
+        Text::new().text("I've been running for: ").code(uptime)
     }
 
+    #[command(
+        name = "!uptime",
+        usage = "<channel?>",
+        help = "shows how long a channel has been live"
+    )]
     async fn uptime(self: Arc<Self>, msg: Message) -> impl Render {
+        let locale = msg.locale();
         let channel = match msg.args().get("channel") {
             Some(channel) => channel.to_string(),
-            None if msg.is_from_twitch() => msg.streamer_name().await,
-            None => anyhow::bail!("that isn't usable on this transport"),
+            None if msg.is_from_twitch() => msg.streamer_name()?,
+            None => anyhow::bail!(locale.tr("unsupported-transport")),
         };
 
-        let client = msg.state().get::<shook_helix::HelixClient>().await;
+        let client = msg.state().get::<shook_helix::HelixClient>()?;
         if let [stream] = &*client.get_streams([&channel]).await? {
             let uptime = (SystemTime::now() - stream.started_at).as_readable_time();
-            return Ok(Simple {
-                twitch: format!("'{channel}' has been live for: {uptime}"),
-                discord: format!("<https://twitch.tv/{channel}> has been live for: `{uptime}`"),
-            });
+            return Ok(Text::new()
+                .link(format!("https://twitch.tv/{channel}"))
+                .text(" has been live for: ")
+                .code(uptime));
         }
 
-        anyhow::bail!("I don't know")
+        anyhow::bail!(locale.tr("unknown"))
     }
 
+    #[command(name = "!theme", help = "shows the editor theme")]
     async fn theme(self: Arc<Self>, _: Message) -> impl Render {
         let FontsAndTheme {
             theme_url,
@@ -192,12 +202,13 @@ impl Builtin {
             ..
         } = self.get_current_settings().await?;
 
-        Ok(Simple {
-            twitch: format!("'{theme_variant}' from {theme_url}"),
-            discord: format!("`{theme_variant}` from <{theme_url}>"),
-        })
+        Ok(Text::new()
+            .code(theme_variant)
+            .text(" from ")
+            .link(theme_url))
     }
 
+    #[command(name = "!font", help = "shows the editor and terminal fonts")]
     async fn font(self: Arc<Self>, _: Message) -> impl Render {
         let FontsAndTheme {
             editor_font,
@@ -205,14 +216,11 @@ impl Builtin {
             ..
         } = self.get_current_settings().await?;
 
-        Ok(Simple {
-            twitch: format!(
-                "terminal is using: '{editor_font}' and editor is using '{terminal_font}'"
-            ),
-            discord: format!(
-                "terminal is using: `{editor_font}` and editor is using `{terminal_font}`"
-            ),
-        })
+        Ok(Text::new()
+            .text("terminal is using: ")
+            .code(editor_font)
+            .text(" and editor is using ")
+            .code(terminal_font))
     }
 
     async fn get_current_settings(&self) -> anyhow::Result<FontsAndTheme> {
@@ -223,6 +231,7 @@ impl Builtin {
         }
 
         async fn get_gist_files(
+            github: &str,
             id: &str,
             OAuth { token }: &OAuth,
         ) -> anyhow::Result<HashMap<String, File>> {
@@ -238,18 +247,19 @@ impl Builtin {
             ]
             .into_iter()
             .fold(
-                reqwest::Client::new().get(format!("https://api.github.com/gists/{id}")),
+                reqwest::Client::new().get(format!("{github}/gists/{id}")),
                 |req, (k, v)| req.header(k, v),
             )
             .send()
             .await?
+            .error_for_status()?
             .json()
             .await?;
 
             Ok(resp.files)
         }
 
-        let files = get_gist_files(&self.gist_id, &self.oauth).await?;
+        let files = get_gist_files(&self.github, &self.gist_id, &self.oauth).await?;
 
         let file = files
             .get("vscode settings.json")
@@ -266,3 +276,178 @@ struct FontsAndTheme {
     theme_url: String,
     theme_variant: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use shook_core::{
+        prelude::State,
+        testing::{run_transcript, Canned, Mock as _, MockMessage, StandIn, TestBinding},
+    };
+
+    use super::*;
+
+    // github and twitch are both pointed at `endpoint`
+    async fn binding(endpoint: Option<String>) -> TestBinding {
+        let mut state = State::default();
+        state.insert(crate::config::Builtin {
+            github_oauth_token: shook_config::Ephemeral::key("SHAKEN_GITHUB_OAUTH_TOKEN")
+                .with_value("hunter2"),
+            settings_gist_id: String::from("gist"),
+            github_endpoint: endpoint.clone(),
+        });
+        state.insert(shook_helix::HelixClient::new_with_ep(
+            endpoint,
+            "client",
+            "Bearer token",
+        ));
+        Builtin::bind.mock_with_state(state).await
+    }
+
+    fn say(resp: &[Response]) -> &str {
+        match resp {
+            [Response::Say(s)] => s,
+            resp => panic!("expected one say, got {resp:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn transcript() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/transcripts/builtin.yaml");
+        run_transcript::<MockMessage>(binding(None).await, path)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn settings_gist() {
+        let settings = serde_json::json!({
+            "editor_font": "Fira Code",
+            "terminal_font": "Iosevka",
+            "theme_url": "https://example.com/theme",
+            "theme_variant": "dark",
+        });
+        let body = serde_json::json!({
+            "files": {
+                "vscode settings.json": {
+                    "content": settings.to_string(),
+                    "raw_url": "https://example.com/raw",
+                }
+            }
+        });
+        let server = StandIn::builder()
+            .get("/gists/gist", Canned::json(&body))
+            .start()
+            .await
+            .unwrap();
+
+        let mut binding = binding(Some(server.url())).await;
+        binding.send_message::<MockMessage>("!theme").await;
+        assert_eq!(
+            say(&binding.get_response()),
+            "dark from https://example.com/theme"
+        );
+
+        binding.send_message::<MockMessage>("!font").await;
+        let resp = binding.get_response();
+        assert!(
+            say(&resp).contains("Fira Code") && say(&resp).contains("Iosevka"),
+            "{resp:?}"
+        );
+
+        let req = &server.requests()[0];
+        assert_eq!(req.header("authorization"), Some("token hunter2"));
+        assert_eq!(req.header("accept"), Some("application/vnd.github+json"));
+    }
+
+    #[tokio::test]
+    async fn settings_gist_errors() {
+        let body = serde_json::json!({ "files": {} });
+        let server = StandIn::builder()
+            .get("/gists/gist", Canned::json(&body))
+            .start()
+            .await
+            .unwrap();
+
+        let mut binding = binding(Some(server.url())).await;
+        binding.send_message::<MockMessage>("!theme").await;
+        let resp = binding.get_response();
+        assert!(
+            matches!(&*resp, [Response::Problem(s)] if s.contains("cannot find settings")),
+            "{resp:?}"
+        );
+
+        drop(server);
+        let server = StandIn::builder()
+            .get("/gists/gist", Canned::status(404))
+            .start()
+            .await
+            .unwrap();
+        let mut binding = self::binding(Some(server.url())).await;
+        binding.send_message::<MockMessage>("!font").await;
+        let resp = binding.get_response();
+        assert!(matches!(&*resp, [Response::Problem(..)]), "{resp:?}");
+    }
+
+    #[tokio::test]
+    #[command(
+        name = "!uptime",
+        usage = "<channel?>",
+        help = "shows how long a channel has been live"
+    )]
+    async fn uptime() {
+        let body = serde_json::json!({
+            "data": [{
+                "id": "1",
+                "user_id": "2",
+                "user_name": "museun",
+                "game_id": "3",
+                "title": "a stream",
+                "viewer_count": 4,
+                "started_at": "2022-08-20T12:00:00Z",
+            }]
+        });
+        let server = StandIn::builder()
+            .get("/streams", Canned::json(&body))
+            .start()
+            .await
+            .unwrap();
+
+        let mut binding = binding(Some(server.url())).await;
+        binding.send_message::<MockMessage>("!uptime museun").await;
+        let resp = binding.get_response();
+        assert!(
+            say(&resp).starts_with("https://twitch.tv/museun has been live for: "),
+            "{resp:?}"
+        );
+        assert_eq!(
+            server.requests()[0].query.as_deref(),
+            Some("user_login=museun")
+        );
+    }
+
+    #[tokio::test]
+    async fn uptime_offline() {
+        let body = serde_json::json!({ "data": [] });
+        let server = StandIn::builder()
+            .get("/streams", Canned::json(&body))
+            .start()
+            .await
+            .unwrap();
+
+        let mut binding = binding(Some(server.url())).await;
+        binding.send_message::<MockMessage>("!uptime museun").await;
+        let resp = binding.get_response();
+        assert!(matches!(&*resp, [Response::Problem(..)]), "{resp:?}");
+
+        drop(server);
+        let server = StandIn::builder()
+            .get("/streams", Canned::status(500))
+            .start()
+            .await
+            .unwrap();
+        let mut binding = self::binding(Some(server.url())).await;
+        binding.send_message::<MockMessage>("!uptime museun").await;
+        let resp = binding.get_response();
+        assert!(matches!(&*resp, [Response::Problem(..)]), "{resp:?}");
+    }
+}
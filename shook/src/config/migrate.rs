@@ -0,0 +1,134 @@
+use anyhow::Context;
+use serde_yaml::{Mapping, Value};
+
+// this is the version `Config` is written as, add a migration when bumping it
+pub const CONFIG_VERSION: u64 = 1;
+
+// each step upgrades from the version at its index. a file without a `version` is 0
+const MIGRATIONS: &[fn(&mut Mapping) -> anyhow::Result<()>] = &[unversioned];
+
+const _: () = assert!(MIGRATIONS.len() as u64 == CONFIG_VERSION);
+
+// this returns whether anything changed, so the file only gets rewritten when it has to
+pub fn upgrade(config: &mut Mapping) -> anyhow::Result<bool> {
+    let key = Value::from("version");
+    let version = match config.get(&key) {
+        Some(version) => version
+            .as_u64()
+            .context("'version' must be a positive number")?,
+        None => 0,
+    };
+
+    if version == CONFIG_VERSION {
+        return Ok(false);
+    }
+
+    if version > CONFIG_VERSION {
+        anyhow::bail!("the config is version {version}, but this only knows up to {CONFIG_VERSION}")
+    }
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        log::info!("upgrading the config from version {i} to {}", i + 1);
+        migration(config).with_context(|| format!("cannot upgrade the config from version {i}"))?;
+    }
+
+    // the version goes first so it is the first thing seen in the file
+    let rest = std::mem::take(config)
+        .into_iter()
+        .filter(|(k, _)| *k != key);
+    config.insert(key.clone(), Value::from(CONFIG_VERSION));
+    config.extend(rest);
+    Ok(true)
+}
+
+// files from before the version key have the same layout as version 1
+fn unversioned(_config: &mut Mapping) -> anyhow::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use shook_core::config::Storage;
+
+    fn mapping(yaml: &str) -> Mapping {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn unversioned_is_upgraded() {
+        let mut config = mapping("twitch: {name: shaken_bot}\ncrates: {}");
+        assert!(upgrade(&mut config).unwrap());
+
+        let keys = config
+            .keys()
+            .map(|k| k.as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ["version", "twitch", "crates"]);
+        assert_eq!(config["version"], Value::from(CONFIG_VERSION));
+        assert_eq!(
+            config["twitch"],
+            Value::Mapping(mapping("name: shaken_bot"))
+        );
+
+        // the current version is left alone
+        let before = config.clone();
+        assert!(!upgrade(&mut config).unwrap());
+        assert_eq!(config, before);
+    }
+
+    #[test]
+    fn newer_is_rejected() {
+        let mut config = mapping(&format!("version: {}", CONFIG_VERSION + 1));
+        let err = upgrade(&mut config).unwrap_err();
+        assert!(err.to_string().contains("only knows up to"), "{err}");
+
+        let mut config = mapping("version: -1");
+        assert!(upgrade(&mut config).is_err());
+    }
+
+    #[tokio::test]
+    async fn original_is_backed_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.yaml");
+
+        // the secrets are read from the environment, and the ephemeral ones are removed once read
+        let secrets = || {
+            for key in ["PASSWORD", "CLIENT_ID", "CLIENT_SECRET"] {
+                std::env::set_var(format!("SHOOK_MIGRATE_TEST_{key}"), "hunter2");
+            }
+        };
+        let config = mapping(
+            "
+            twitch:
+              address: localhost:6667
+              name: shaken_bot
+              password: SHOOK_MIGRATE_TEST_PASSWORD
+              channel: '#museun'
+            helix:
+              client_id: SHOOK_MIGRATE_TEST_CLIENT_ID
+              client_secret: SHOOK_MIGRATE_TEST_CLIENT_SECRET
+            ",
+        );
+        assert!(!config.contains_key(Value::from("version")));
+        let original = serde_yaml::to_string(&config).unwrap();
+        std::fs::write(&path, &original).unwrap();
+
+        secrets();
+        let loaded = Config::load(&path).await.unwrap();
+        assert_eq!(loaded.version, CONFIG_VERSION);
+
+        let upgraded = mapping(&std::fs::read_to_string(&path).unwrap());
+        assert_eq!(upgraded["version"], Value::from(CONFIG_VERSION));
+
+        let backups = Storage::for_path(&path).unwrap().backups();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(std::fs::read_to_string(&backups[0]).unwrap(), original);
+
+        // an upgraded file isn't written again
+        secrets();
+        Config::load(&path).await.unwrap();
+        assert_eq!(Storage::for_path(&path).unwrap().backups().len(), 1);
+    }
+}
@@ -0,0 +1,208 @@
+use std::path::{Path, PathBuf};
+
+use serde_yaml::{Mapping, Value};
+use shook_config::{Ephemeral, Secret};
+use shook_core::{
+    callable::DispatchConfig, config::Storage, locale::LocaleConfig, prelude::Prefix, ConfigPath,
+};
+
+mod migrate;
+pub use migrate::CONFIG_VERSION;
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Spotify {
+    pub client_id: Secret,
+    pub client_secret: Ephemeral,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct AnotherViewer {
+    pub endpoint: Secret,
+    pub bearer_token: Ephemeral,
+    pub filter_patterns_path: PathBuf,
+}
+
+impl ConfigPath for AnotherViewer {
+    fn file_path(&self) -> &Path {
+        &self.filter_patterns_path
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Youtube {
+    pub endpoint: Secret,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct UserDefined {
+    pub user_defined_path: PathBuf,
+}
+
+impl Default for UserDefined {
+    fn default() -> Self {
+        Self {
+            user_defined_path: PathBuf::from("./data/shook.db"),
+        }
+    }
+}
+
+impl ConfigPath for UserDefined {
+    fn file_path(&self) -> &Path {
+        &self.user_defined_path
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Registry {
+    pub registry_path: PathBuf,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self {
+            registry_path: PathBuf::from("./data/registry.yaml"),
+        }
+    }
+}
+
+impl ConfigPath for Registry {
+    fn file_path(&self) -> &Path {
+        &self.registry_path
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Catalog {
+    pub catalog_path: PathBuf,
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self {
+            catalog_path: PathBuf::from("./data/catalog.yaml"),
+        }
+    }
+}
+
+impl ConfigPath for Catalog {
+    fn file_path(&self) -> &Path {
+        &self.catalog_path
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Builtin {
+    pub github_oauth_token: Ephemeral,
+    pub settings_gist_id: String,
+    // this defaults to https://api.github.com
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub github_endpoint: Option<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Default)]
+pub struct Crates {
+    // this defaults to https://crates.io/api/v1/crates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Config {
+    // older files are upgraded when loaded, see `migrate`
+    #[serde(default)]
+    pub version: u64,
+
+    pub twitch: shook_twitch::config::Config,
+    pub helix: shook_helix::config::Config,
+
+    // a missing section turns off whatever uses it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discord: Option<shook_twilight::config::Config>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spotify: Option<self::Spotify>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub another_viewer: Option<self::AnotherViewer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub youtube: Option<self::Youtube>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub builtin: Option<self::Builtin>,
+
+    #[serde(default)]
+    pub crates: self::Crates,
+    #[serde(default)]
+    pub user_defined: self::UserDefined,
+    #[serde(default)]
+    pub registry: self::Registry,
+    #[serde(default)]
+    pub catalog: self::Catalog,
+
+    #[serde(default)]
+    pub dispatch: DispatchConfig,
+    #[serde(default)]
+    pub locale: LocaleConfig,
+}
+
+impl Config {
+    const KEY: &'static str = "config";
+
+    // an older file is upgraded in place, and the old one is kept as a backup next to it
+    pub async fn load(path: impl Into<PathBuf> + Send) -> anyhow::Result<Self> {
+        let storage = Storage::for_path(path)?;
+        let mut config: Mapping = storage.load(Self::KEY).await?;
+        if migrate::upgrade(&mut config)? {
+            storage.save(Self::KEY, &config).await?;
+            log::info!(
+                "upgraded {} to version {CONFIG_VERSION}",
+                storage.path().display()
+            );
+        }
+        Ok(serde_yaml::from_value(Value::Mapping(config))?)
+    }
+
+    pub fn default_config() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            twitch: shook_twitch::config::Config {
+                address: String::from("irc.chat.twitch.tv:6667"),
+                name: String::from("shaken_bot"),
+                password: Ephemeral::key("SHAKEN_TWITCH_OAUTH_TOKEN"),
+                channel: String::from("#museun"),
+                prefix: Prefix::default(),
+                announce: vec![String::from("#museun")],
+            },
+            discord: Some(shook_twilight::config::Config {
+                oauth_token: Ephemeral::key("SHAKEN_DISCORD_OAUTH_TOKEN"),
+                prefix: Prefix::default(),
+                announce: Vec::new(),
+            }),
+            helix: shook_helix::config::Config {
+                client_id: Secret::key("SHAKEN_TWITCH_CLIENT_ID"),
+                client_secret: Ephemeral::key("SHAKEN_TWITCH_CLIENT_SECRET"),
+                endpoint: None,
+            },
+            spotify: Some(Spotify {
+                client_id: Secret::key("SHAKEN_SPOTIFY_CLIENT_ID"),
+                client_secret: Ephemeral::key("SHAKEN_SPOTIFY_CLIENT_SECRET"),
+            }),
+            another_viewer: Some(AnotherViewer {
+                endpoint: Secret::key("SHAKEN_REVERSE_PROXY"),
+                bearer_token: Ephemeral::key("SHAKEN_BRAIN_GENERATE_TOKEN"),
+                filter_patterns_path: PathBuf::from("./data/filter_patterns.yaml"),
+            }),
+            builtin: Some(Builtin {
+                github_oauth_token: Ephemeral::key("SHAKEN_GITHUB_OAUTH_TOKEN"),
+                settings_gist_id: String::from("6f7b1d5e0c293e927959f74c884b039c"),
+                github_endpoint: None,
+            }),
+            youtube: Some(Youtube {
+                endpoint: Secret::key("SHAKEN_REVERSE_PROXY"),
+            }),
+            crates: Crates::default(),
+            user_defined: UserDefined::default(),
+            registry: Registry::default(),
+            catalog: Catalog::default(),
+            dispatch: DispatchConfig::default(),
+            locale: LocaleConfig::default(),
+        }
+    }
+}
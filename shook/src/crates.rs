@@ -17,50 +17,31 @@ impl Match {
 impl Render for Match {
     fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
         let crate_ = self.crate_();
-        let exact = matches!(self, Self::Closest { .. })
-            .then_some("# (this was the closest match I could find)")
+        let closest = matches!(self, Self::Closest { .. })
+            .then_some(" (this was the closest match I could find)")
             .unwrap_or_default();
 
-        match flavor {
-            RenderFlavor::Twitch => crate_.format_for_twitch(exact).render(flavor),
-            RenderFlavor::Discord => crate_.format_for_discord(exact).render(flavor),
-            _ => todo!(),
-        }
-    }
-}
-
-impl Crate {
-    fn unwrap_unknown(op: &Option<String>) -> &str {
-        op.as_deref().unwrap_or("unknown").trim()
-    }
-
-    fn format_for_twitch(&self, exact: &str) -> impl Render {
-        let data = format!("{} = {} {}", self.name, self.max_version, exact);
-        [&self.description, &self.repository, &self.documentation]
-            .into_iter()
-            .filter_map(Option::as_ref)
-            .zip(["desc", "repo", "docs"])
-            .fold(Response::say(data), |resp, (opt, ty)| {
-                resp.say(format!("{ty}: {opt}"))
-            })
-            .finish()
-    }
-
-    fn format_for_discord(&self, exact: &str) -> impl Render {
-        indoc::formatdoc!(
-            r#"```toml
-                    {exact}
-                    {name} = {version}```
-                    **description**: {desc}
-                    **repository**: <{repo}>
-                    **documenation**: <{docs}>
-                "#,
-            name = self.name,
-            version = self.max_version,
-            desc = Self::unwrap_unknown(&self.description),
-            repo = Self::unwrap_unknown(&self.repository),
-            docs = Self::unwrap_unknown(&self.documentation)
-        )
+        let fields = [
+            ("description", &crate_.description, false),
+            ("repository", &crate_.repository, true),
+            ("documentation", &crate_.documentation, true),
+        ]
+        .into_iter()
+        .filter_map(|(name, field, link)| Some((name, field.as_deref()?.trim(), link)))
+        .map(|(name, field, link)| {
+            let text = Text::new().bold(name).text(": ");
+            if link {
+                text.link(field)
+            } else {
+                text.text(field)
+            }
+        });
+
+        Text::new()
+            .code(format!("{} = {}", crate_.name, crate_.max_version))
+            .text(closest)
+            .list(fields)
+            .render(flavor)
     }
 }
 
@@ -80,13 +61,15 @@ struct CratesClient {
 }
 
 impl CratesClient {
-    fn new() -> Self {
+    const BASE_URL: &'static str = "https://crates.io/api/v1/crates";
+
+    fn new_with_ep(ep: impl Into<Option<String>>) -> Self {
         Self {
             client: reqwest::Client::builder()
                 .user_agent(shook_core::USER_AGENT)
                 .build()
                 .expect("valid client"),
-            ep: String::from("https://crates.io/api/v1/crates"),
+            ep: ep.into().unwrap_or_else(|| String::from(Self::BASE_URL)),
         }
     }
 
@@ -97,33 +80,150 @@ impl CratesClient {
         }
         let query = &&[("page", "1"), ("per_page", "1"), ("q", query)];
         let resp = self.client.get(&self.ep).query(query).send().await?;
-        Ok(resp.json::<Resp>().await?.crates)
+        Ok(resp.error_for_status()?.json::<Resp>().await?.crates)
     }
 }
 
 pub struct Crates;
 
 impl Crates {
+    // these are the commands `bind` binds, so they can be listed without connecting to anything
+    pub fn declared() -> Vec<Command> {
+        vec![Self::crates_command()]
+    }
+
     pub async fn bind(state: GlobalState) -> anyhow::Result<SharedCallable> {
-        state.insert(CratesClient::new()).await;
+        let crate::config::Crates { endpoint } = state.get_owned()?;
+        state.insert(CratesClient::new_with_ep(endpoint));
         Ok(Binding::create(state, Self)
             .await
-            .bind(Self::crates)
+            .bind_cmd(Self::crates_command(), Self::crates)
             .into_callable())
     }
 
+    #[command(
+        name = "!crates",
+        usage = "<name> --exact",
+        help = "looks up a crate on crates.io",
+        aliases = ["!crate"]
+    )]
     async fn crates(self: Arc<Self>, msg: Message) -> impl Render {
         let arg = &msg.args()["name"];
 
-        let client: &CratesClient = &*msg.state().get().await;
+        let client = msg.state().get::<CratesClient>()?;
         let crates = client.get(arg).await?;
 
-        anyhow::ensure!(!crates.is_empty(), "I cannot find anything for: {arg}");
+        anyhow::ensure!(
+            !crates.is_empty(),
+            msg.tr_with("crates.not-found", &[("name", &arg)])
+        );
         let head = crates.first().cloned();
 
-        Ok(crates
-            .into_iter()
-            .find(|c| c.exact_match)
-            .map_or_else(|| Match::Closest(head.unwrap()), Match::Exact))
+        let found = crates.into_iter().find(|c| c.exact_match);
+        if found.is_none() && msg.args().flag("exact") {
+            anyhow::bail!(msg.tr_with("crates.no-exact-match", &[("name", &arg)]))
+        }
+
+        Ok(found.map_or_else(|| Match::Closest(head.unwrap()), Match::Exact))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shook_core::{
+        prelude::State,
+        testing::{run_transcript, Canned, Mock as _, MockMessage, StandIn, TestBinding},
+    };
+
+    use super::*;
+
+    async fn binding(server: &StandIn) -> TestBinding {
+        let mut state = State::default();
+        state.insert(crate::config::Crates {
+            endpoint: Some(format!("{}/api/v1/crates", server.url())),
+        });
+        Crates::bind.mock_with_state(state).await
+    }
+
+    fn found(name: &str, exact_match: bool) -> serde_json::Value {
+        serde_json::json!({
+            "name": name,
+            "max_version": "1.0.0",
+            "description": "a crate",
+            "documentation": null,
+            "repository": null,
+            "exact_match": exact_match,
+        })
+    }
+
+    #[tokio::test]
+    async fn exact_match() {
+        let body = serde_json::json!({ "crates": [found("serde", true)] });
+        let server = StandIn::builder()
+            .get("/api/v1/crates", Canned::json(&body))
+            .start()
+            .await
+            .unwrap();
+
+        let mut binding = binding(&server).await;
+        binding.send_message::<MockMessage>("!crates serde").await;
+
+        let resp = binding.get_response();
+        assert!(
+            matches!(&*resp, [Response::Say(s)] if s.contains("serde = 1.0.0") && !s.contains("closest")),
+            "{resp:?}"
+        );
+        assert_eq!(
+            server.requests()[0].query.as_deref(),
+            Some("page=1&per_page=1&q=serde")
+        );
+    }
+
+    #[tokio::test]
+    async fn errors_are_reported() {
+        let body = serde_json::json!({ "crates": [found("serde_json", false)] });
+        let server = StandIn::builder()
+            .get("/api/v1/crates", Canned::json(&body))
+            .start()
+            .await
+            .unwrap();
+
+        let mut binding = binding(&server).await;
+        binding
+            .send_message::<MockMessage>("!crates serde --exact")
+            .await;
+        let resp = binding.get_response();
+        assert!(
+            matches!(&*resp, [Response::Problem(s)] if s.contains("exact match")),
+            "{resp:?}"
+        );
+
+        // a server error is a problem too, rather than a crash
+        drop(server);
+        let server = StandIn::builder()
+            .get("/api/v1/crates", Canned::status(503))
+            .start()
+            .await
+            .unwrap();
+        let mut binding = self::binding(&server).await;
+        binding.send_message::<MockMessage>("!crates serde").await;
+        let resp = binding.get_response();
+        assert!(matches!(&*resp, [Response::Problem(..)]), "{resp:?}");
+    }
+
+    #[tokio::test]
+    async fn transcript() {
+        // every search finds the same crate, which is never an exact match
+        let body = serde_json::json!({ "crates": [found("serde_json", false)] });
+        let server = StandIn::builder()
+            .get("/api/v1/crates", Canned::json(&body))
+            .start()
+            .await
+            .unwrap();
+
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/transcripts/crates.yaml");
+        run_transcript::<MockMessage>(binding(&server).await, path)
+            .await
+            .unwrap();
     }
 }
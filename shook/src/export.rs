@@ -0,0 +1,339 @@
+use std::fmt::Write as _;
+
+use shook_core::{
+    callable::{Command, Cooldown, Permission},
+    help::{Description, Registry},
+    prelude::Locale,
+    render::{split, Limits, RenderFlavor, Response},
+};
+
+// user defined commands are listed under this namespace
+pub const USER_DEFINED: &str = "custom";
+
+// a command declared with `#[command]` is listed even when the registry file doesn't have it.
+// otherwise the registry's entry is used, like it is when binding, but never with a looser permission
+pub fn merge_declared(registry: &Registry, declared: &[(&str, Vec<Command>)]) -> Registry {
+    let find = |name: &str| {
+        declared
+            .iter()
+            .flat_map(|(_, commands)| commands)
+            .find(|cmd| &*cmd.command == name)
+    };
+
+    let mut merged = Registry::default();
+    for (namespace, descriptions) in registry.namespaces() {
+        for desc in descriptions.iter() {
+            let mut desc = desc.clone();
+            if let Some(cmd) = find(&desc.command) {
+                desc.permission = desc.permission.max(cmd.permission);
+            }
+            merged.add(namespace, desc)
+        }
+    }
+
+    for (namespace, commands) in declared {
+        for cmd in commands {
+            if registry.find_command(&cmd.command).is_none() {
+                merged.add(namespace, cmd.clone())
+            }
+        }
+    }
+    merged
+}
+
+// this uses headers and lists rather than tables, so it also works on discord.
+// a discord message can only be 2000 characters though, see `discord_messages`
+pub fn markdown(registry: &Registry) -> String {
+    let mut out = String::from("# Commands\n");
+
+    for (namespace, descriptions) in registry.namespaces() {
+        let _ = writeln!(&mut out, "\n## {namespace}\n");
+        for entry in descriptions.iter().flat_map(entries) {
+            let _ = writeln!(
+                &mut out,
+                "- `{}` - {}",
+                entry.usage,
+                markdown_escape(entry.description)
+            );
+
+            let details = entry
+                .details()
+                .into_iter()
+                .map(|(key, val)| format!("{key}: {val}"))
+                .collect::<Vec<_>>();
+            let _ = writeln!(&mut out, "  {}", details.join(" · "));
+        }
+    }
+
+    out
+}
+
+// this splits the markdown into messages that fit on discord
+pub fn discord_messages(markdown: &str) -> Vec<String> {
+    let limits = Limits::for_flavor(RenderFlavor::Discord).with_max_messages(usize::MAX);
+    // nothing is left out, so the locale isn't used
+    split(
+        vec![Response::Say(markdown.to_string())],
+        limits,
+        &Locale::default(),
+    )
+    .into_iter()
+    .filter_map(|resp| match resp {
+        Response::Say(text) => Some(text),
+        _ => None,
+    })
+    .collect()
+}
+
+// a single page with its own styles, so it can be published as is
+pub fn html(registry: &Registry) -> String {
+    let mut out = String::from(HEADER);
+
+    for (namespace, descriptions) in registry.namespaces() {
+        let namespace = html_escape(namespace);
+        let _ = writeln!(
+            &mut out,
+            r#"<section id="{namespace}"><h2>{namespace}</h2>"#
+        );
+        out.push_str(
+            "<table><tr><th>command</th><th>description</th><th>aliases</th><th>permission</th><th>cooldown</th></tr>\n",
+        );
+
+        for entry in descriptions.iter().flat_map(entries) {
+            let aliases = entry
+                .aliases
+                .iter()
+                .map(|alias| format!("<code>{}</code>", html_escape(alias)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let cooldown = entry.cooldown.as_ref().map(describe_cooldown);
+
+            let _ = writeln!(
+                &mut out,
+                "<tr><td><code>{}</code></td><td>{}</td><td>{aliases}</td><td>{}</td><td>{}</td></tr>",
+                html_escape(&entry.usage),
+                html_escape(entry.description),
+                entry.permission,
+                cooldown.as_deref().unwrap_or_default()
+            );
+        }
+
+        out.push_str("</table></section>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+const HEADER: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Commands</title>
+<style>
+body { font-family: sans-serif; max-width: 60em; margin: 2em auto; padding: 0 1em; color: #222; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 0.3em 0.6em; border-bottom: 1px solid #ddd; vertical-align: top; }
+code { background: #f2f2f2; padding: 0 0.2em; }
+</style>
+</head>
+<body>
+<h1>Commands</h1>
+"#;
+
+// a subcommand gets its own entry, after its parent
+struct Entry<'a> {
+    usage: String,
+    description: &'a str,
+    aliases: &'a [String],
+    permission: Permission,
+    cooldown: &'a Option<Cooldown>,
+}
+
+impl<'a> Entry<'a> {
+    fn details(&self) -> Vec<(&'static str, String)> {
+        let mut details = vec![("permission", self.permission.to_string())];
+        if !self.aliases.is_empty() {
+            let aliases = self
+                .aliases
+                .iter()
+                .map(|alias| format!("`{alias}`"))
+                .collect::<Vec<_>>();
+            details.push(("aliases", aliases.join(" ")));
+        }
+        if let Some(cooldown) = self.cooldown {
+            details.push(("cooldown", describe_cooldown(cooldown)));
+        }
+        details
+    }
+}
+
+fn entries(desc: &Description) -> Vec<Entry<'_>> {
+    let parent = Entry {
+        usage: desc.usage().to_string(),
+        description: desc.description(),
+        aliases: &desc.aliases,
+        permission: desc.permission,
+        cooldown: &desc.cooldown,
+    };
+
+    std::iter::once(parent)
+        .chain(desc.subcommands.iter().map(|sub| Entry {
+            usage: format!("{} {}", desc.command, sub.usage()),
+            description: sub.description(),
+            aliases: &sub.aliases,
+            permission: sub.permission,
+            cooldown: &sub.cooldown,
+        }))
+        .collect()
+}
+
+fn describe_cooldown(cooldown: &Cooldown) -> String {
+    let scopes = [
+        (cooldown.global, "globally"),
+        (cooldown.user, "per user"),
+        (cooldown.channel, "per channel"),
+    ]
+    .into_iter()
+    .filter_map(|(secs, scope)| Some(format!("{}s {scope}", secs?)))
+    .collect::<Vec<_>>();
+
+    let mut out = scopes.join(", ");
+    if !cooldown.is_empty() && !cooldown.bypass.is_everyone() {
+        let _ = write!(&mut out, " ({} and up can skip it)", cooldown.bypass);
+    }
+    out
+}
+
+fn markdown_escape(input: &str) -> String {
+    input.chars().fold(String::new(), |mut out, c| {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '~' | '|' | '<' | '>' | '[' | ']'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+        out
+    })
+}
+
+fn html_escape(input: &str) -> String {
+    input.chars().fold(String::new(), |mut out, c| {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+        out
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use shook_core::prelude::Command;
+
+    use super::*;
+
+    fn registry() -> Registry {
+        let mut registry = Registry::default();
+        registry.add(
+            "builtin",
+            Command::new("!help")
+                .help("shows <help> for a command")
+                .alias("!h")
+                .usage("<command?>")
+                .unwrap(),
+        );
+        registry.add(
+            "builtin",
+            Command::new("!theme")
+                .help("shows the current theme")
+                .permission(Permission::Subscriber)
+                .cooldown(Cooldown::user(30)),
+        );
+        registry
+    }
+
+    #[test]
+    fn markdown() {
+        assert_eq!(
+            super::markdown(&registry()),
+            "# Commands\n\n## builtin\n\n\
+            - `!help <command?>` - shows \\<help\\> for a command\n  permission: everyone · aliases: `!h`\n\
+            - `!theme` - shows the current theme\n  permission: subscriber · cooldown: 30s per user (moderator and up can skip it)\n"
+        );
+    }
+
+    #[test]
+    fn declared_commands_are_merged() {
+        let declared = [
+            (
+                "builtin",
+                vec![
+                    Command::new("!help").permission(Permission::Vip),
+                    Command::new("!version").help("shows the version"),
+                ],
+            ),
+            ("crates", vec![Command::new("!crates").alias("!crate")]),
+        ];
+
+        let merged = merge_declared(&registry(), &declared);
+        let names = merged
+            .namespaces()
+            .into_iter()
+            .flat_map(|(namespace, desc)| desc.iter().map(move |desc| (namespace, &*desc.command)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                ("builtin", "!help"),
+                ("builtin", "!theme"),
+                ("builtin", "!version"),
+                ("crates", "!crates")
+            ]
+        );
+
+        // the registry's entry is kept, but it cannot be looser than the code
+        let help = merged.find_command("!help").unwrap();
+        assert_eq!(help.description(), "shows <help> for a command");
+        assert_eq!(help.permission, Permission::Vip);
+        let theme = merged.find_command("!theme").unwrap();
+        assert_eq!(theme.permission, Permission::Subscriber);
+    }
+
+    #[test]
+    fn discord_messages() {
+        let markdown = super::markdown(&registry());
+        assert_eq!(super::discord_messages(&markdown), [markdown.trim()]);
+
+        let mut registry = registry();
+        for i in 0..100 {
+            registry.add(
+                "many",
+                Command::new(&format!("!command{i}")).help("this does something"),
+            );
+        }
+
+        let markdown = super::markdown(&registry);
+        let messages = super::discord_messages(&markdown);
+        assert!(messages.len() > 1);
+        assert!(messages.iter().all(|msg| msg.chars().count() <= 2000));
+        assert!(messages.concat().contains("!command99"));
+    }
+
+    #[test]
+    fn html_is_escaped() {
+        let html = super::html(&registry());
+        assert!(
+            html.contains("<td>shows &lt;help&gt; for a command</td>"),
+            "{html}"
+        );
+        assert!(html.contains("<td><code>!h</code></td>"), "{html}");
+        assert!(html.ends_with("</html>\n"));
+    }
+}
@@ -15,6 +15,19 @@ pub use what_song::WhatSong;
 
 pub mod config;
 
+pub mod export;
+
+// the commands each module declares with `#[command]`, under the namespace its binding uses
+pub fn declared_commands() -> Vec<(&'static str, Vec<shook_core::callable::Command>)> {
+    vec![
+        ("another_viewer", AnotherViewer::declared()),
+        ("builtin", Builtin::declared()),
+        ("crates", Crates::declared()),
+        ("user_defined", UserDefined::declared()),
+        ("what_song", WhatSong::declared()),
+    ]
+}
+
 mod queue;
 
 include!(concat!(env!("OUT_DIR"), "/", "version.rs"));
@@ -1,6 +1,6 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
-use shook_core::{prelude::*, PersistFromConfig};
+use shook_core::{help::Description, prelude::*, IterExt, PersistFromConfig};
 use tokio::sync::Mutex;
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, PartialOrd, Eq, Ord)]
@@ -101,14 +101,38 @@ impl UserDefinedState {
 
 impl PersistFromConfig for UserDefinedState {
     type ConfigPath = crate::config::UserDefined;
+    const KEY: &'static str = "user_defined";
 }
 
+// every write of the file also makes a backup, so uses are counted here and written this often
+const SAVE_USES: Duration = Duration::from_secs(10 * 60);
+
 pub struct UserDefined {
     user_defined_state: Mutex<UserDefinedState>,
+    uses: Mutex<HashMap<String, usize>>,
     state: GlobalState,
 }
 
 impl UserDefined {
+    // these are the commands `bind` binds, so they can be listed without connecting to anything
+    pub fn declared() -> Vec<shook_core::callable::Command> {
+        vec![
+            Self::group()
+                .subcommand(Self::add_command())
+                .subcommand(Self::update_command())
+                .subcommand(Self::remove_command())
+                .subcommand(Self::alias_command()),
+            Self::commands_command(),
+        ]
+    }
+
+    // the commands that change what is stored are all under `!cmd`, so they don't take up names users may want
+    fn group() -> shook_core::callable::Command {
+        shook_core::callable::Command::new("!cmd")
+            .help("manages the user defined commands")
+            .permission(Permission::Moderator)
+    }
+
     pub async fn bind(state: GlobalState) -> anyhow::Result<SharedCallable> {
         let user_defined_state = UserDefinedState::load_from_file(&state)
             .await
@@ -118,167 +142,335 @@ impl UserDefined {
             state.clone(),
             Self {
                 user_defined_state,
+                uses: Mutex::default(),
                 state,
             },
         )
         .await
-        .bind(Self::add)
-        .bind(Self::update)
-        .bind(Self::remove)
-        .bind(Self::alias)
-        .bind(Self::commands)
+        .bind_cmd(Self::group().subcommand(Self::add_command()), Self::add)
+        .bind_cmd(
+            Self::group().subcommand(Self::update_command()),
+            Self::update,
+        )
+        .bind_cmd(
+            Self::group().subcommand(Self::remove_command()),
+            Self::remove,
+        )
+        .bind_cmd(Self::group().subcommand(Self::alias_command()), Self::alias)
+        .bind_cmd(Self::commands_command(), Self::commands)
         .listen(Self::lookup)
+        .every(SAVE_USES, Self::save_uses)
         .into_callable())
     }
 
-    async fn add(self: Arc<Self>, msg: Message) -> impl Render {
-        msg.require_elevation()?;
+    // the stored commands, sorted by name. each one's description is what it says
+    pub async fn descriptions(state: &GlobalState) -> anyhow::Result<Vec<Description>> {
+        let user_defined_state = UserDefinedState::load_from_file(state).await?;
+
+        let mut commands = user_defined_state.get_all().collect::<Vec<_>>();
+        commands.sort_unstable_by(|left, right| left.name.cmp(&right.name));
+
+        Ok(commands
+            .into_iter()
+            .map(|cmd| Description {
+                command: cmd.name.clone(),
+                usage: None,
+                description: cmd.body.clone(),
+                aliases: user_defined_state
+                    .aliases
+                    .iter()
+                    .filter(|(name, _)| *name == cmd.name)
+                    .map(|(_, alias)| alias.clone())
+                    .collect(),
+                permission: Permission::Everyone,
+                cooldown: None,
+                subcommands: Vec::new(),
+            })
+            .collect())
+    }
 
-        let name = Self::validate_command(&msg.args()["name"])?;
+    #[command(
+        name = "add",
+        usage = "<name> <body..>",
+        help = "adds a command that says the body",
+        permission = "moderator"
+    )]
+    async fn add(self: Arc<Self>, msg: Message) -> impl Render {
+        let name = Self::validate_command(&msg, &msg.args()["name"])?;
+        let shown = msg.display_command(&name);
         let body = &msg.args()["body"];
-        anyhow::ensure!(!body.is_empty(), "the command body cannot be empty");
+        anyhow::ensure!(!body.is_empty(), msg.tr("user-defined.empty-body"));
+        Self::ensure_not_builtin(&msg, &name).await?;
 
-        if !self
-            .user_defined_state
-            .lock()
-            .await
-            .insert(Command::new(name, body, msg.sender_name()))
-        {
-            return Ok(Simple {
-                twitch: format!("{name} already exists"),
-                discord: format!("`{name}` already exists"),
-            });
+        let cmd = Command::new(name, body, msg.sender_name());
+        if !self.change(move |state| state.insert(cmd)).await? {
+            return Ok(msg.tr_code("user-defined.exists", &[("command", &shown)]));
         }
 
-        self.sync().await?;
-
-        Ok(Simple {
-            twitch: format!("created {name} -> {body}"),
-            discord: format!("created `{name}` -> `{body}`"),
-        })
+        Ok(msg.tr_code(
+            "user-defined.created",
+            &[("command", &shown), ("body", body)],
+        ))
     }
 
+    #[command(
+        name = "update",
+        usage = "<name> <body..>",
+        help = "changes what a command says",
+        permission = "moderator"
+    )]
     async fn update(self: Arc<Self>, msg: Message) -> impl Render {
-        msg.require_elevation()?;
-
-        let name = Self::validate_command(&msg.args()["name"])?;
+        let name = Self::validate_command(&msg, &msg.args()["name"])?;
+        let shown = msg.display_command(&name);
         let body = &msg.args()["body"];
 
-        anyhow::ensure!(!body.is_empty(), "the command body cannot be empty");
+        anyhow::ensure!(!body.is_empty(), msg.tr("user-defined.empty-body"));
 
-        if !self
-            .user_defined_state
-            .lock()
-            .await
-            .update(name, |cmd| cmd.body = body.to_string())
-        {
-            return Ok(Simple {
-                twitch: format!("{name} doesn't exists"),
-                discord: format!("`{name}` doesn't exists"),
-            });
+        let updated = {
+            let body = body.to_string();
+            self.change(move |state| state.update(&name, |cmd| cmd.body = body.clone()))
+                .await?
+        };
+        if !updated {
+            return Ok(msg.tr_code("user-defined.not-found", &[("command", &shown)]));
         }
 
-        self.sync().await?;
-
-        Ok(Simple {
-            twitch: format!("updated {name} -> {body}"),
-            discord: format!("updated `{name}` -> `{body}`"),
-        })
+        Ok(msg.tr_code(
+            "user-defined.updated",
+            &[("command", &shown), ("body", body)],
+        ))
     }
 
+    #[command(
+        name = "remove",
+        usage = "<name>",
+        help = "removes a command and its aliases",
+        permission = "moderator"
+    )]
     async fn remove(self: Arc<Self>, msg: Message) -> impl Render {
-        msg.require_elevation()?;
+        let name = Self::validate_command(&msg, &msg.args()["name"])?;
+        let shown = msg.display_command(&name);
 
-        let name = Self::validate_command(&msg.args()["name"])?;
-
-        if !self.user_defined_state.lock().await.remove(name) {
-            return Ok(Simple {
-                twitch: format!("{name} wasn't found"),
-                discord: format!("`{name}` wasn't found"),
-            });
+        if !self.change(move |state| state.remove(&name)).await? {
+            return Ok(msg.tr_code("user-defined.not-found", &[("command", &shown)]));
         }
 
-        self.sync().await?;
-
-        Ok(Simple {
-            twitch: format!("removed: {name}"),
-            discord: format!("removed: `{name}`"),
-        })
+        Ok(msg.tr_code("user-defined.removed", &[("command", &shown)]))
     }
 
+    #[command(
+        name = "alias",
+        usage = "<from> <to>",
+        help = "lets a command also be used by another name",
+        permission = "moderator"
+    )]
     async fn alias(self: Arc<Self>, msg: Message) -> impl Render {
-        msg.require_elevation()?;
+        let from = Self::validate_command(&msg, &msg.args()["from"])?;
+        let to = Self::validate_command(&msg, &msg.args()["to"])?;
+        let (shown_from, shown_to) = (msg.display_command(&from), msg.display_command(&to));
+        Self::ensure_not_builtin(&msg, &to).await?;
+
+        let aliased = self
+            .change(move |state| match state.has(&from) {
+                true => Some(state.alias(&from, &to)),
+                false => None,
+            })
+            .await?;
+        match aliased {
+            None => {
+                let args = [("command", &*shown_from)];
+                return Ok(msg.tr_code("user-defined.not-found", &args));
+            }
+            Some(false) => {
+                return Ok(msg.tr_code("user-defined.exists", &[("command", &shown_to)]));
+            }
+            Some(true) => {}
+        }
 
-        let from = Self::validate_command(&msg.args()["from"])?;
-        let to = Self::validate_command(&msg.args()["to"])?;
+        Ok(msg.tr_code(
+            "user-defined.aliased",
+            &[("from", &shown_from), ("to", &shown_to)],
+        ))
+    }
 
-        {
-            let mut state = self.user_defined_state.lock().await;
-            if !state.has(from) {
-                return Ok(Simple {
-                    twitch: format!("{from} was not found"),
-                    discord: format!("`{from}` was not found"),
-                });
-            }
+    #[command(name = "!commands", help = "lists the user defined commands")]
+    async fn commands(self: Arc<Self>, msg: Message) -> impl Render {
+        let state = self.user_defined_state.lock().await;
+        state
+            .get_all()
+            .map(|c| msg.display_command(&c.name))
+            .join_with(' ')
+    }
 
-            if !state.alias(from, to) {
-                return Ok(Simple {
-                    twitch: format!("{to} already exists"),
-                    discord: format!("`{to}` already exists"),
-                });
-            }
+    async fn lookup(self: Arc<Self>, msg: Message) -> impl Render {
+        if !msg.is_command() {
+            return None;
         }
 
-        self.sync().await?;
+        let cmd = msg.prefixes().canonical(msg.command());
+        if Self::is_builtin(&msg, &cmd) {
+            return None;
+        }
 
-        Ok(Simple {
-            twitch: format!("aliased {from} to {to}"),
-            discord: format!("aliased `{from}` to `{to}`"),
-        })
+        let (name, body) = {
+            let state = self.user_defined_state.lock().await;
+            let cmd = state.get_by_name(&cmd)?;
+            (cmd.name.clone(), cmd.body.clone())
+        };
+
+        *self.uses.lock().await.entry(name).or_default() += 1;
+        Some(body)
     }
 
-    async fn commands(self: Arc<Self>, _: Message) -> impl Render {
-        let state = self.user_defined_state.lock().await;
-        let (mut resp, line) = state.get_all().map(|c| &*c.name).enumerate().fold(
-            (Response::builder(), String::new()),
-            |(mut resp, mut out), (i, cmd)| {
-                if i > 0 && i % 20 == 0 {
-                    resp = resp.say(std::mem::take(&mut out))
-                }
-                if !out.is_empty() {
-                    out.push(' ')
-                }
-                out.push_str(cmd);
-                (resp, out)
-            },
-        );
+    async fn save_uses(self: Arc<Self>, _: GlobalState) {
+        let uses = std::mem::take(&mut *self.uses.lock().await);
+        if uses.is_empty() {
+            return;
+        }
 
-        if !line.is_empty() {
-            resp = resp.say(line)
+        let count = move |state: &mut UserDefinedState| {
+            for (name, uses) in &uses {
+                state.update(name, |cmd| cmd.uses += uses);
+            }
+        };
+        if let Err(err) = self.change(count).await {
+            log::warn!("cannot save the uses of the user defined commands: {err}")
         }
-        resp
     }
 
-    async fn lookup(self: Arc<Self>, msg: Message) -> impl Render {
-        let cmd = msg.data().split_ascii_whitespace().next()?;
+    // this changes what is stored rather than saving the copy here, so another writer isn't undone
+    async fn change<R>(
+        &self,
+        change: impl FnOnce(&mut UserDefinedState) -> R + Send + 'static,
+    ) -> anyhow::Result<R>
+    where
+        R: Send + 'static,
+    {
         let mut state = self.user_defined_state.lock().await;
-        if !state.has(cmd) {
-            return None;
-        }
+        let (stored, out) = UserDefinedState::update_file(&self.state, change).await?;
+        *state = stored;
+        Ok(out)
+    }
 
-        state.update(cmd, |cmd| cmd.uses += 1);
-        let cmd = state.get_by_name(cmd).expect("cmd should exist");
-        Some(cmd.body.clone())
+    // user defined commands cannot shadow the commands in the registry
+    async fn ensure_not_builtin(msg: &Message, name: &str) -> anyhow::Result<()> {
+        let shown = msg.display_command(name);
+        anyhow::ensure!(
+            !Self::is_builtin(msg, name),
+            msg.tr_with("user-defined.builtin", &[("command", &shown)])
+        );
+        Ok(())
     }
 
-    async fn sync(&self) -> anyhow::Result<()> {
-        let uds = self.user_defined_state.lock().await;
-        uds.save_to_file(&self.state).await
+    fn is_builtin(msg: &Message, name: &str) -> bool {
+        match msg.state().try_get::<SharedRegistry>() {
+            Some(registry) => registry.find_command(name).is_some(),
+            None => false,
+        }
     }
 
-    fn validate_command(name: &str) -> anyhow::Result<&str> {
-        anyhow::ensure!(name.starts_with('!'), "you must prefix commands with !");
-        anyhow::ensure!(name.len() > 1, "the command name cannot be empty");
+    // names can be written with any of the channel's prefixes, but they're stored with `!`
+    fn validate_command(msg: &Message, name: &str) -> anyhow::Result<String> {
+        let name = msg.prefixes().canonical(name);
+        anyhow::ensure!(name.len() > 1, msg.tr("user-defined.empty-name"));
         Ok(name)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use shook_core::{
+        help::Registry,
+        prelude::State,
+        testing::{run_transcript, Mock as _, MockMessage, TestBinding},
+    };
+
+    use super::*;
+
+    async fn binding(dir: &tempfile::TempDir) -> TestBinding {
+        let path = dir.path().join("user_defined.json");
+        let empty = UserDefinedState::default();
+        std::fs::write(&path, serde_json::to_vec(&empty).unwrap()).unwrap();
+
+        // the registry entry only describes these, their usage and permission come from the binding
+        let mut registry = Registry::default();
+        registry.add(
+            "user_defined",
+            ["add", "update", "remove", "alias"]
+                .into_iter()
+                .fold(shook_core::callable::Command::new("!cmd"), |cmd, name| {
+                    cmd.subcommand(shook_core::callable::Command::new(name))
+                }),
+        );
+
+        let mut state = State::default();
+        state.insert(SharedRegistry::new(registry));
+        state.insert(crate::config::UserDefined {
+            user_defined_path: path,
+        });
+        UserDefined::bind.mock_with_state(state).await
+    }
+
+    #[tokio::test]
+    async fn only_moderators_can_add() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut binding = binding(&dir).await;
+        binding
+            .send_message::<MockMessage>("!cmd add !hi hello")
+            .await;
+        let resp = binding.get_response();
+        assert!(matches!(&*resp, [Response::Problem(..)]), "{resp:?}");
+
+        binding.send_message::<MockMessage>("!hi").await;
+        assert!(binding.get_response().is_empty());
+
+        let mut binding = binding.with_moderator();
+        binding
+            .send_message::<MockMessage>("!cmd add !hi hello")
+            .await;
+        let resp = binding.get_response();
+        assert!(
+            matches!(&*resp, [Response::Say(s)] if s.starts_with("created")),
+            "{resp:?}"
+        );
+
+        binding.send_message::<MockMessage>("!hi").await;
+        assert_eq!(
+            binding.get_response(),
+            [Response::Say(String::from("hello"))]
+        );
+    }
+
+    #[tokio::test]
+    async fn transcript() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/transcripts/user_defined.yaml");
+        run_transcript::<MockMessage>(binding(&dir).await, path)
+            .await
+            .unwrap();
+    }
+
+    // the uses are only written every `SAVE_USES`, so using a command doesn't make a backup each time
+    #[tokio::test(start_paused = true)]
+    async fn uses_are_saved_later() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("user_defined.json");
+        let uses = || {
+            let state: UserDefinedState =
+                serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+            state.map.get("!hi").map(|cmd| cmd.uses)
+        };
+
+        let mut binding = binding(&dir).await.with_moderator();
+        binding
+            .send_message::<MockMessage>("!cmd add !hi hello")
+            .await;
+        binding.send_message::<MockMessage>("!hi").await;
+        binding.send_message::<MockMessage>("!hi").await;
+        assert_eq!(uses(), Some(0));
+
+        tokio::time::sleep(SAVE_USES + Duration::from_secs(1)).await;
+        assert_eq!(uses(), Some(2));
+    }
+}
@@ -1,4 +1,4 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use crate::queue::Queue;
 use anyhow::Context;
@@ -7,8 +7,7 @@ use rspotify::{
     prelude::{Id, OAuthClient},
     AuthCodeSpotify, Credentials, OAuth,
 };
-use shook_core::{prelude::*, IterExt};
-use shook_helix::HelixClient;
+use shook_core::{prelude::*, schedule::Schedule, IterExt};
 use tokio::sync::Mutex;
 
 #[derive(Debug, Clone)]
@@ -98,24 +97,13 @@ struct Spotify {
 impl Spotify {
     const HISTORY_LIMIT: usize = 5;
 
-    async fn create(
-        config: crate::config::Spotify,
-        helix: HelixClient,
-        streamer: StreamerName,
-    ) -> anyhow::Result<Self> {
+    async fn create(config: &crate::config::Spotify) -> anyhow::Result<Self> {
         let spotify = SpotifyClient::new(&config.client_id, &config.client_secret).await?;
         let queue = Arc::new(Mutex::new(Queue::with_capacity(Self::HISTORY_LIMIT)));
-
-        tokio::spawn({
-            let queue = queue.clone();
-            let spotify = spotify.clone();
-            Self::update_loop(queue, helix, spotify, streamer)
-        });
-
         Ok(Self { spotify, queue })
     }
 
-    async fn current(&self) -> impl Render {
+    async fn current(&self, locale: &Locale) -> impl Render {
         if let Some(song) = self.queue.lock().await.last() {
             return Ok(Self::format_song(song));
         }
@@ -125,28 +113,18 @@ impl Spotify {
             self.queue.lock().await.push(song);
             return Ok(out);
         }
-        anyhow::bail!("I don't know")
+        anyhow::bail!(locale.tr("unknown"))
     }
 
-    async fn previous(&self) -> impl Render {
+    async fn previous(&self, locale: &Locale) -> impl Render {
         let queue = self.queue.lock().await;
-        let song = queue.last_nth(1).with_context(|| "I don't know")?;
+        let song = queue.last_nth(1).with_context(|| locale.tr("unknown"))?;
         Ok(Self::format_song(song))
     }
 
-    async fn update_loop(
-        queue: Arc<Mutex<Queue<Song>>>,
-        twitch: HelixClient,
-        spotify: SpotifyClient,
-        streamer: StreamerName,
-    ) {
-        loop {
-            if let Ok([_stream]) = twitch.get_streams([&streamer.0]).await.as_deref() {
-                if let Some(song) = spotify.try_get_song().await {
-                    queue.lock().await.push(song);
-                }
-            }
-            tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+    async fn update(&self) {
+        if let Some(song) = self.spotify.try_get_song().await {
+            self.queue.lock().await.push(song);
         }
     }
 
@@ -168,14 +146,15 @@ struct YoutubeItem {
 
 struct Youtube {
     client: reqwest::Client,
-    ep: String,
+    // the endpoint is looked up for each request, so a new config is used right away
+    config: Watch<crate::config::Youtube>,
 }
 
 impl Youtube {
-    async fn create(config: crate::config::Youtube) -> Self {
+    fn create(config: Watch<crate::config::Youtube>) -> Self {
         Self {
             client: reqwest::Client::new(),
-            ep: config.endpoint.into_string(),
+            config,
         }
     }
 
@@ -198,9 +177,13 @@ impl Youtube {
     }
 
     async fn get(&self, ep: &str) -> anyhow::Result<YoutubeItem> {
+        let config = self
+            .config
+            .current()
+            .with_context(|| "youtube is not configured")?;
         Ok(self
             .client
-            .get(format!("{}/{ep}", self.ep))
+            .get(format!("{}/{ep}", config.endpoint.inner()))
             .send()
             .await?
             .error_for_status()?
@@ -224,51 +207,78 @@ enum Mode {
 }
 
 impl WhatSong {
-    pub async fn bind(state: GlobalState) -> anyhow::Result<SharedCallable> {
-        let youtube_config = state.get_owned().await;
-        let spotify_config = state.get_owned().await;
+    // these are the commands `bind` binds, so they can be listed without connecting to anything
+    pub fn declared() -> Vec<Command> {
+        vec![
+            Self::current_command(),
+            Self::previous_command(),
+            Self::swap_command(),
+        ]
+    }
 
-        let helix_client = state.get_owned().await;
-        let streamer = state.get_owned().await;
+    pub async fn bind(state: GlobalState) -> anyhow::Result<SharedCallable> {
+        // spotify only needs its credentials to log in
+        let spotify_config = state
+            .subscribe::<crate::config::Spotify>()
+            .current()
+            .with_context(|| "spotify is not configured")?;
 
         let this = Self {
             mode: <Arc<Mutex<_>>>::default(),
-            youtube: Youtube::create(youtube_config).await,
-            spotify: Spotify::create(spotify_config, helix_client, streamer).await?,
+            youtube: Youtube::create(state.subscribe()),
+            spotify: Spotify::create(&spotify_config).await?,
         };
 
+        let poll = Schedule::every(Duration::from_secs(10)).only_while_live();
+
         Ok(Binding::create(state, this)
             .await
-            .bind(Self::current)
-            .bind(Self::previous)
-            .bind(Self::swap)
+            .bind_cmd(Self::current_command(), Self::current)
+            .bind_cmd(Self::previous_command(), Self::previous)
+            .bind_cmd(Self::swap_command(), Self::swap)
+            .schedule(poll, Self::poll_spotify)
             .into_callable())
     }
 
-    async fn current(self: Arc<Self>, _: Message) -> impl Render {
+    async fn poll_spotify(self: Arc<Self>, _: GlobalState) {
+        self.spotify.update().await
+    }
+
+    #[command(name = "!current", help = "shows the song that is playing")]
+    async fn current(self: Arc<Self>, msg: Message) -> impl Render {
+        let locale = msg.locale();
         let mode = self.mode.lock().await;
         match &*mode {
-            Mode::Spotify => self.spotify.current().await.boxed(),
+            Mode::Spotify => self.spotify.current(&locale).await.boxed(),
             Mode::Youtube => self.youtube.current().await.boxed(),
-            Mode::None => "I don't know".boxed(),
+            Mode::None => locale.tr("unknown").boxed(),
         }
     }
 
-    async fn previous(self: Arc<Self>, _: Message) -> impl Render {
+    #[command(
+        name = "!previous",
+        help = "shows the song that played before this one"
+    )]
+    async fn previous(self: Arc<Self>, msg: Message) -> impl Render {
+        let locale = msg.locale();
         let mode = self.mode.lock().await;
         match &*mode {
-            Mode::Spotify => self.spotify.previous().await.boxed(),
+            Mode::Spotify => self.spotify.previous(&locale).await.boxed(),
             Mode::Youtube => self.youtube.previous().await.boxed(),
-            Mode::None => "I don't know".boxed(),
+            Mode::None => locale.tr("unknown").boxed(),
         }
     }
 
+    #[command(
+        name = "!swap",
+        usage = "<service>",
+        help = "changes where songs come from: spotify, youtube or none",
+        permission = "broadcaster"
+    )]
     async fn swap(self: Arc<Self>, msg: Message) -> impl Render {
         const INVALID_MODE: &str =
             "invalid mode. try one of these: [spotify], [youtube], [none | off]";
 
-        msg.require_broadcaster()?;
-
         let mode = match &msg.args()["service"] {
             "spotify" => Mode::Spotify,
             "youtube" => Mode::Youtube,
@@ -286,3 +296,84 @@ impl WhatSong {
         Ok(format!("switched from '{}' to '{}'", f(old), f(mode)).boxed())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use shook_config::Secret;
+    use shook_core::{
+        prelude::State,
+        testing::{Canned, StandIn},
+    };
+
+    use super::*;
+
+    fn youtube(server: &StandIn) -> Youtube {
+        let mut state = State::default();
+        state.insert(crate::config::Youtube {
+            endpoint: Secret::key("SHAKEN_REVERSE_PROXY").with_value(&server.url()),
+        });
+        Youtube::create(GlobalState::new(state).subscribe())
+    }
+
+    fn item(ts: u64) -> serde_json::Value {
+        serde_json::json!({
+            "title": "a song",
+            "id": "dQw4w9WgXcQ",
+            "ts": ts.to_string(),
+            "duration": "300",
+        })
+    }
+
+    fn now() -> u64 {
+        std::time::SystemTime::UNIX_EPOCH
+            .elapsed()
+            .unwrap()
+            .as_secs()
+    }
+
+    #[tokio::test]
+    async fn youtube_songs() {
+        let server = StandIn::builder()
+            .get("/youtube/current", Canned::json(&item(now())))
+            .get("/youtube/previous", Canned::json(&item(now() - 600)))
+            .start()
+            .await
+            .unwrap();
+
+        let youtube = youtube(&server);
+        let expected = [Response::Say(String::from(
+            "https://youtu.be/dQw4w9WgXcQ - a song",
+        ))];
+        assert_eq!(
+            youtube.current().await.render(RenderFlavor::Twitch),
+            expected
+        );
+        assert_eq!(
+            youtube.previous().await.render(RenderFlavor::Twitch),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn youtube_errors() {
+        // a song that already ended isn't the current one
+        let server = StandIn::builder()
+            .get("/youtube/current", Canned::json(&item(now() - 600)))
+            .get("/youtube/previous", Canned::status(502))
+            .start()
+            .await
+            .unwrap();
+
+        let youtube = youtube(&server);
+        assert!(youtube
+            .current()
+            .await
+            .render(RenderFlavor::Twitch)
+            .is_empty());
+        assert!(youtube
+            .previous()
+            .await
+            .render(RenderFlavor::Twitch)
+            .is_empty());
+    }
+}
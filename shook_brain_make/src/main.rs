@@ -1,6 +1,7 @@
 use std::path::{Path, PathBuf};
 
 use gumdrop::Options;
+use shook_config::SafeFile;
 use shook_markov::Brain;
 
 // TODO train
@@ -30,9 +31,12 @@ pub trait BrainExt {
 impl BrainExt for Brain {
     fn save(&self, path: &Path) -> anyhow::Result<()> {
         log::debug!("saving brain to: {}", path.display());
-        let writer = std::io::BufWriter::new(std::fs::File::create(path)?);
-        let enc = zstd::Encoder::new(writer, 0)?.auto_finish();
-        bincode::serialize_into(enc, self)?;
+        SafeFile::new(path).write(|writer| {
+            let mut enc = zstd::Encoder::new(writer, 0)?;
+            bincode::serialize_into(&mut enc, self)?;
+            enc.finish()?;
+            Ok(())
+        })?;
         log::trace!("saved");
         Ok(())
     }
@@ -9,6 +9,7 @@ use std::{
 };
 use tower_http::auth::require_authorization::RequireAuthorizationLayer;
 
+use shook_config::SafeFile;
 use shook_markov::Brain;
 
 mod handlers;
@@ -25,18 +26,26 @@ pub use managed::ManagedBrain;
 pub const SAVE_DURATION: Duration = Duration::from_secs(5 * 60);
 pub const GENERATE_TIMEOUT: Duration = Duration::from_secs(5);
 
+// a corrupted brain falls back to its newest good backup
 pub async fn load(path: impl Into<PathBuf> + Send) -> anyhow::Result<Brain> {
     let path = path.into();
     tokio::task::spawn_blocking(move || {
-        let reader = std::io::BufReader::new(std::fs::File::open(path)?);
-        let dec = zstd::Decoder::new(reader)?;
-        let element = bincode::deserialize_from(dec)?;
-        anyhow::Result::<_>::Ok(element)
+        SafeFile::new(path).load(|path| {
+            let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+            let dec = zstd::Decoder::new(reader)?;
+            let element = bincode::deserialize_from(dec)?;
+            anyhow::Result::<_>::Ok(element)
+        })
     })
     .await
     .unwrap()
 }
 
+// this replaces the brain with one of its backups, the newest one if `backup` is `None`
+pub fn restore(path: &Path, backup: Option<&Path>) -> anyhow::Result<PathBuf> {
+    SafeFile::new(path).restore(backup)
+}
+
 pub trait BrainExt {
     fn save(&self, path: &Path) -> anyhow::Result<()>;
 }
@@ -44,9 +53,12 @@ pub trait BrainExt {
 impl BrainExt for Brain {
     fn save(&self, path: &Path) -> anyhow::Result<()> {
         log::debug!("saving brain to: {}", path.display());
-        let writer = std::io::BufWriter::new(std::fs::File::create(path)?);
-        let enc = zstd::Encoder::new(writer, 0)?.auto_finish();
-        bincode::serialize_into(enc, self)?;
+        SafeFile::new(path).write(|writer| {
+            let mut enc = zstd::Encoder::new(writer, 0)?;
+            bincode::serialize_into(&mut enc, self)?;
+            enc.finish()?;
+            Ok(())
+        })?;
         log::trace!("saved");
         Ok(())
     }
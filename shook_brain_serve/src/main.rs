@@ -17,6 +17,14 @@ struct Config {
     /// address to listen on
     #[options(default = "localhost:8000", meta = "<addr>")]
     address: String,
+
+    /// replace the brain with its newest backup, or the one given, then exit
+    #[options(no_short)]
+    restore: bool,
+
+    /// the backup to restore
+    #[options(no_short, meta = "<path>")]
+    backup: Option<PathBuf>,
 }
 
 async fn load(path: impl AsRef<Path> + Send) -> anyhow::Result<Messaging> {
@@ -42,12 +50,18 @@ async fn main() -> anyhow::Result<()> {
     .init()?;
 
     let config = Config::parse_args_default_or_exit();
-    let bearer = get_env_var("SHAKEN_BRAIN_BEARER_TOKEN")?;
 
     let file = get_env_var("SHAKEN_BRAIN_FILE")
         .map(PathBuf::from)
         .unwrap_or(config.file);
 
+    if config.restore {
+        shook_brain_serve::restore(&file, config.backup.as_deref())?;
+        return Ok(());
+    }
+
+    let bearer = get_env_var("SHAKEN_BRAIN_BEARER_TOKEN")?;
+
     log::info!("loading brain from {}", file.display());
     let brain = load(&file).await?;
     log::debug!("loaded brain");
@@ -14,6 +14,12 @@ impl Ephemeral {
         }
     }
 
+    // this is for tests, where the value isn't in the environment
+    pub fn with_value(mut self, value: &str) -> Self {
+        self.inner = Cow::from(value.to_string());
+        self
+    }
+
     pub fn env_key(&self) -> &str {
         &self.key
     }
@@ -4,6 +4,9 @@ pub use secret::Secret;
 mod ephemeral;
 pub use ephemeral::Ephemeral;
 
+mod safe_file;
+pub use safe_file::{SafeFile, DEFAULT_BACKUPS};
+
 fn redact(s: &str) -> impl std::fmt::Debug {
     struct NoDebug(String);
     impl std::fmt::Debug for NoDebug {
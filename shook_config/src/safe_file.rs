@@ -0,0 +1,303 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+
+pub const DEFAULT_BACKUPS: usize = 5;
+
+// a write goes to a temporary file which then replaces the real one, so a crash leaves either the old or the new file.
+// the replaced file is kept next to it as `<name>.<unix millis>.bak`
+#[derive(Clone, Debug)]
+pub struct SafeFile {
+    path: PathBuf,
+    backups: usize,
+}
+
+impl SafeFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            backups: DEFAULT_BACKUPS,
+        }
+    }
+
+    // 0 turns off backups
+    pub const fn with_backups(mut self, backups: usize) -> Self {
+        self.backups = backups;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write_bytes(&self, data: &[u8]) -> anyhow::Result<()> {
+        self.write(|w| Ok(w.write_all(data)?))
+    }
+
+    pub fn write(
+        &self,
+        write: impl FnOnce(&mut dyn Write) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let tmp = self.with_suffix("tmp");
+        let result = (|| {
+            let file = File::create(&tmp)?;
+            let mut writer = std::io::BufWriter::new(&file);
+            write(&mut writer)?;
+            writer.flush()?;
+            drop(writer);
+            file.sync_all()?;
+            anyhow::Result::<_>::Ok(())
+        })();
+        if let Err(err) = result {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(err.context(format!("cannot write {}", self.path.display())));
+        }
+
+        self.backup();
+        std::fs::rename(&tmp, &self.path)
+            .with_context(|| format!("cannot replace {}", self.path.display()))?;
+        // the rename isn't durable until the directory is synced
+        if let Err(err) = sync_dir(self.dir()) {
+            log::warn!("cannot sync {}: {err}", self.dir().display())
+        }
+
+        self.prune();
+        Ok(())
+    }
+
+    // when the file cannot be read, this tries each backup from newest to oldest
+    pub fn load<T>(&self, read: impl Fn(&Path) -> anyhow::Result<T>) -> anyhow::Result<T> {
+        let err = match read(&self.path) {
+            Ok(val) => return Ok(val),
+            Err(err) => err,
+        };
+
+        for backup in self.backups() {
+            match read(&backup) {
+                Ok(val) => {
+                    log::warn!(
+                        "cannot load {} ({err:#}), using {} instead",
+                        self.path.display(),
+                        backup.display()
+                    );
+                    return Ok(val);
+                }
+                Err(err) => log::warn!("cannot load backup {}: {err:#}", backup.display()),
+            }
+        }
+        Err(err)
+    }
+
+    // this replaces the file with a backup, the newest one if `backup` is `None`. the current file is backed up first
+    pub fn restore(&self, backup: Option<&Path>) -> anyhow::Result<PathBuf> {
+        let backup = match backup {
+            Some(backup) => backup.to_path_buf(),
+            None => self
+                .backups()
+                .into_iter()
+                .next()
+                .with_context(|| format!("{} has no backups", self.path.display()))?,
+        };
+
+        let data =
+            std::fs::read(&backup).with_context(|| format!("cannot read {}", backup.display()))?;
+        self.write_bytes(&data)?;
+        log::info!("restored {} from {}", self.path.display(), backup.display());
+        Ok(backup)
+    }
+
+    // these are sorted newest first
+    pub fn backups(&self) -> Vec<PathBuf> {
+        let name = self.file_name();
+        let prefix = format!("{name}.");
+
+        let entries = match std::fs::read_dir(self.dir()) {
+            Ok(entries) => entries,
+            Err(..) => return Vec::new(),
+        };
+
+        let mut backups = entries
+            .flatten()
+            .filter_map(|entry| {
+                let file_name = entry.file_name();
+                let stamp = file_name
+                    .to_str()?
+                    .strip_prefix(&prefix)?
+                    .strip_suffix(".bak")?
+                    .parse::<u128>()
+                    .ok()?;
+                Some((stamp, entry.path()))
+            })
+            .collect::<Vec<_>>();
+
+        backups.sort_unstable_by(|(left, _), (right, _)| right.cmp(left));
+        backups.into_iter().map(|(_, path)| path).collect()
+    }
+
+    // this is where the next backup goes
+    pub fn next_backup(&self) -> PathBuf {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        self.with_suffix(&format!("{now}.bak"))
+    }
+
+    // this removes all but the newest backups
+    pub fn prune(&self) {
+        for old in self.backups().into_iter().skip(self.backups) {
+            if let Err(err) = std::fs::remove_file(&old) {
+                log::warn!("cannot remove old backup {}: {err}", old.display())
+            }
+        }
+    }
+
+    fn backup(&self) {
+        if self.backups == 0 || !self.path.exists() {
+            return;
+        }
+
+        let backup = self.next_backup();
+        if backup.exists() {
+            return;
+        }
+
+        // the old file is replaced rather than changed, so a link keeps it without a copy
+        let result = std::fs::hard_link(&self.path, &backup)
+            .or_else(|_| std::fs::copy(&self.path, &backup).map(drop));
+        if let Err(err) = result {
+            log::warn!("cannot back up {}: {err}", self.path.display())
+        }
+    }
+
+    fn with_suffix(&self, suffix: &str) -> PathBuf {
+        self.path
+            .with_file_name(format!("{}.{suffix}", self.file_name()))
+    }
+
+    fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    fn dir(&self) -> &Path {
+        match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // backups are named by the millisecond, so writes in the same one would share a backup
+    fn write(file: &SafeFile, data: &str) {
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        file.write_bytes(data.as_bytes()).unwrap();
+    }
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn replace_leaves_a_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = SafeFile::new(dir.path().join("data.json"));
+
+        write(&file, "first");
+        assert!(file.backups().is_empty());
+
+        write(&file, "second");
+        assert_eq!(read(file.path()), "second");
+
+        let backups = file.backups();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(read(&backups[0]), "first");
+
+        // only the file and its backup are left
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn backups_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = SafeFile::new(dir.path().join("data.json")).with_backups(2);
+
+        for n in 0..5 {
+            write(&file, &n.to_string());
+        }
+
+        let backups = file.backups().iter().map(|p| read(p)).collect::<Vec<_>>();
+        assert_eq!(backups, ["3", "2"]);
+
+        let file = file.with_backups(0);
+        write(&file, "5");
+        assert!(file.backups().is_empty());
+    }
+
+    #[test]
+    fn load_falls_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = SafeFile::new(dir.path().join("data.json"));
+        let parse = |path: &Path| Ok(read(path).parse::<u32>()?);
+
+        write(&file, "1");
+        write(&file, "2");
+        assert_eq!(file.load(parse).unwrap(), 2);
+
+        std::fs::write(file.path(), "garbage").unwrap();
+        assert_eq!(file.load(parse).unwrap(), 1);
+
+        // the file is left alone, that is what `restore` is for
+        assert_eq!(read(file.path()), "garbage");
+
+        for backup in file.backups() {
+            std::fs::write(backup, "more garbage").unwrap();
+        }
+        assert!(file.load(parse).is_err());
+    }
+
+    #[test]
+    fn restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = SafeFile::new(dir.path().join("data.json"));
+        assert!(file.restore(None).is_err());
+
+        write(&file, "1");
+        write(&file, "2");
+        write(&file, "3");
+        let newest = file.backups()[0].clone();
+        let oldest = file.backups().pop().unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        assert_eq!(file.restore(None).unwrap(), newest);
+        assert_eq!(read(file.path()), "2");
+
+        // the file that was replaced was backed up
+        assert_eq!(read(&file.backups()[0]), "3");
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        file.restore(Some(&oldest)).unwrap();
+        assert_eq!(read(file.path()), "1");
+    }
+}
@@ -1,26 +1,117 @@
 use std::{
     collections::{HashMap, HashSet},
-    str::FromStr,
+    fmt::Display,
+    time::Duration,
 };
 
+use crate::locale::Locale;
+
 #[derive(Default, Debug, Clone)]
 pub struct Arguments {
+    // this is the text of each value, see `Value`'s `Display`
     pub map: HashMap<String, String>,
+    values: HashMap<String, Value>,
 }
 
 impl Arguments {
+    fn insert(&mut self, key: &str, value: Value) {
+        self.map.insert(key.to_string(), value.to_string());
+        self.values.insert(key.to_string(), value);
+    }
+
     pub fn get(&self, key: &str) -> Option<&str> {
         self.map.get(key).map(|s| &**s)
     }
 
-    pub fn get_parsed<T>(&self, key: &str) -> Option<anyhow::Result<T>>
-    where
-        T: FromStr,
-        T::Err: Into<anyhow::Error>,
-    {
-        self.get(key)
-            .map(<str>::parse)
-            .map(|c| c.map_err(Into::into))
+    // values are checked against their declared type before the handler sees them
+    pub fn value(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    // this converts the typed value, so asking for a u8 from a `<count:u32>` can fail but a string never does
+    pub fn get_parsed<T: FromValue>(&self, key: &str) -> Option<anyhow::Result<T>> {
+        self.value(key).map(T::from_value)
+    }
+
+    // a `--switch` is only in the map when it was given
+    pub fn flag(&self, key: &str) -> bool {
+        matches!(self.value(key), Some(Value::Switch))
+    }
+
+    pub fn get_duration(&self, key: &str) -> Option<Duration> {
+        match self.value(key)? {
+            Value::Duration(dur) => Some(*dur),
+            _ => None,
+        }
+    }
+}
+
+// a value that matched its declared type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    String(String),
+    Number(i128),
+    // this is without the leading @
+    User(String),
+    Duration(Duration),
+    // this is spelled the way the usage has it
+    Choice(String),
+    Switch,
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::String(s) | Self::User(s) | Self::Choice(s) => f.write_str(s),
+            Self::Number(n) => write!(f, "{n}"),
+            // a duration is shown as whole seconds
+            Self::Duration(dur) => write!(f, "{}", dur.as_secs()),
+            Self::Switch => f.write_str("true"),
+        }
+    }
+}
+
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> anyhow::Result<Self>;
+}
+
+macro_rules! from_number {
+    ($($ty:ident)*) => {
+        $(impl FromValue for $ty {
+            fn from_value(value: &Value) -> anyhow::Result<Self> {
+                match value {
+                    Value::Number(n) => $ty::try_from(*n).map_err(|_| {
+                        anyhow::anyhow!("{n} does not fit in a {}", stringify!($ty))
+                    }),
+                    value => anyhow::bail!("'{value}' is not a number"),
+                }
+            }
+        })*
+    };
+}
+from_number!(u8 u16 u32 u64 usize i8 i16 i32 i64 isize);
+
+impl FromValue for String {
+    fn from_value(value: &Value) -> anyhow::Result<Self> {
+        Ok(value.to_string())
+    }
+}
+
+impl FromValue for Duration {
+    fn from_value(value: &Value) -> anyhow::Result<Self> {
+        match value {
+            Value::Duration(dur) => Ok(*dur),
+            value => anyhow::bail!("'{value}' is not a duration"),
+        }
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> anyhow::Result<Self> {
+        match value {
+            Value::Switch => Ok(true),
+            value => anyhow::bail!("'{value}' is not a switch"),
+        }
     }
 }
 
@@ -39,6 +130,38 @@ pub enum Match<T> {
     Match(T),
     NoMatch,
     Exact,
+    Invalid { key: String, expected: Reason },
+    Malformed { reason: Reason },
+}
+
+// a catalog key, and the arguments for its template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reason {
+    pub key: &'static str,
+    pub args: Vec<(&'static str, String)>,
+}
+
+impl Reason {
+    const fn new(key: &'static str) -> Self {
+        Self {
+            key,
+            args: Vec::new(),
+        }
+    }
+
+    fn with(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+
+    pub fn tr(&self, locale: &Locale) -> String {
+        let args = self
+            .args
+            .iter()
+            .map(|(name, value)| (*name, value as &dyn Display))
+            .collect::<Vec<_>>();
+        locale.tr_with(self.key, &args)
+    }
 }
 
 #[derive(Default, Debug)]
@@ -51,90 +174,213 @@ impl ExampleArgs {
     pub fn contains(&self, arg: &Kind) -> bool {
         self.args.iter().any(|ArgType { kind, .. }| kind == arg)
     }
+
+    fn positional(&self) -> impl Iterator<Item = &ArgType> {
+        self.args.iter().filter(|arg| !arg.kind.is_flag())
+    }
+
+    fn flag(&self, name: &str) -> Option<&ArgType> {
+        self.args
+            .iter()
+            .find(|arg| arg.kind.is_flag() && arg.key == name)
+    }
 }
 
 impl ExampleArgs {
-    pub fn extract(&self, mut input: &str) -> Match<HashMap<String, String>> {
-        if input.is_empty() {
+    // the fixed positions are lexed, so they can be "quoted". the text for a trailing
+    // <rest..> is taken as it was written, so its quotes are kept
+    pub fn extract(&self, input: &str) -> Match<Arguments> {
+        let mut map = Arguments::default();
+        let fixed = self
+            .positional()
+            .filter(|arg| arg.kind != Kind::Variadic)
+            .count();
+        let variadic = self.contains(&Kind::Variadic);
+
+        // flags are only looked for if the usage declares some, and a bare `--` ends them
+        let mut flags = self.args.iter().any(|arg| arg.kind.is_flag());
+
+        let mut lexer = Lexer::new(input);
+        let mut all = vec![];
+        let mut segments = vec![];
+        loop {
+            lexer.quotes = !variadic || segments.len() < fixed;
+            let segment = match lexer.next_segment() {
+                Ok(Some(segment)) => segment,
+                Ok(None) => break,
+                Err(reason) => return Match::Malformed { reason },
+            };
+            all.push(segment.clone());
+
+            let name = match segment.value.strip_prefix("--") {
+                Some(name) if flags && !segment.quoted => name,
+                _ => {
+                    segments.push(segment);
+                    continue;
+                }
+            };
+
+            if name.is_empty() {
+                flags = false;
+                continue;
+            }
+
+            if let Err(err) = self.extract_flag(name, &mut lexer, &mut all, &mut map) {
+                return err;
+            }
+        }
+
+        if segments.is_empty() {
             if self.contains(&Kind::Required) {
                 return Match::Required;
             }
-            if !self.args.is_empty()
-                && (!self.contains(&Kind::Optional) && !self.contains(&Kind::Variadic))
-            {
-                return Match::NoMatch;
-            }
             if self.args.is_empty() {
                 return Match::Exact;
             }
+            return Match::Match(map);
         }
 
-        if !input.is_empty() && self.args.is_empty() {
+        if self.positional().next().is_none() {
             return Match::NoMatch;
         }
 
-        use Kind::*;
-        let mut map = HashMap::new();
-        for ArgType { key, kind } in &*self.args {
-            match (kind, input.find(' ')) {
-                (Required | Optional, None) | (Variadic, ..) => {
-                    if !input.is_empty() {
-                        map.insert(key.into(), input.into());
-                    }
-                    break;
+        let mut rest = &*segments;
+        for ArgType { key, kind, ty } in self.positional() {
+            let value = match (kind, rest) {
+                (.., []) => break,
+                (Kind::Variadic, ..) => Segment::join(input, std::mem::take(&mut rest), &all),
+                (.., [head, tail @ ..]) => {
+                    rest = tail;
+                    head.value.clone()
                 }
-                (.., Some(pos)) => {
-                    let (head, tail) = input.split_at(pos);
-                    map.insert(key.into(), head.into());
-                    input = tail.trim();
+            };
+
+            match ty.validate(&value) {
+                Some(value) => map.insert(key, value),
+                None => {
+                    return Match::Invalid {
+                        key: key.into(),
+                        expected: ty.expected(),
+                    }
                 }
-            }
+            };
         }
 
         Match::Match(map)
     }
 
+    fn extract_flag(
+        &self,
+        name: &str,
+        lexer: &mut Lexer<'_>,
+        all: &mut Vec<Segment>,
+        map: &mut Arguments,
+    ) -> Result<(), Match<Arguments>> {
+        let (name, inline) = match name.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (name, None),
+        };
+
+        let ArgType { key, kind, ty } = match self.flag(name) {
+            Some(arg) => arg,
+            None => {
+                let reason = Reason::new("args.unknown-flag").with("name", name);
+                return Err(Match::Malformed { reason });
+            }
+        };
+
+        let value = match (kind, inline) {
+            (Kind::Switch, None) => {
+                map.insert(key, Value::Switch);
+                return Ok(());
+            }
+            (Kind::Switch, Some(..)) => {
+                let reason = Reason::new("args.flag-value").with("key", key);
+                return Err(Match::Malformed { reason });
+            }
+            (.., Some(value)) => value,
+            (.., None) => match lexer.next_segment() {
+                Ok(Some(next)) => {
+                    all.push(next.clone());
+                    next.value
+                }
+                Ok(None) => String::new(),
+                Err(reason) => return Err(Match::Malformed { reason }),
+            },
+        };
+
+        match ty.validate(&value) {
+            Some(value) if !value.to_string().is_empty() => {
+                map.insert(key, value);
+                Ok(())
+            }
+            _ => Err(Match::Invalid {
+                key: format!("--{key}"),
+                expected: ty.expected(),
+            }),
+        }
+    }
+
     pub fn parse(input: &str) -> anyhow::Result<Self> {
-        // <required> <optional?> <rest..>
+        // <required> <optional?> <rest..> <typed:u32> <typed:u32?> --switch --key=u32
         let mut seen = HashSet::new();
-        let mut args = vec![];
+        let mut args: Vec<ArgType> = vec![];
 
         for token in input.split_ascii_whitespace() {
-            let mut append = |arg: &[_]| {
-                let data = &token[1..arg.len() + 1];
-                anyhow::ensure!(seen.insert(data), "{data} was already used");
-                Ok(data.into())
-            };
+            let (key, kind, ty) = match token.strip_prefix("--") {
+                Some(flag) => match flag.split_once('=') {
+                    Some((key, ty)) => (key, Kind::Named, Type::parse(ty)?),
+                    None => (flag, Kind::Switch, Type::String),
+                },
+                None => {
+                    if let Some(ArgType { key, .. }) =
+                        args.iter().find(|a| a.kind == Kind::Variadic)
+                    {
+                        anyhow::bail!("'{token}' cannot come after the variadic <{key}..>")
+                    }
 
-            let all_alpha = move |s: &[u8]| {
-                s.iter()
-                    .all(|d| matches!(d, b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'-'))
-            };
+                    let inner = token
+                        .strip_prefix('<')
+                        .and_then(|s| s.strip_suffix('>'))
+                        .ok_or_else(|| anyhow::anyhow!("invalid pattern: '{token}'"))?;
 
-            let arg = match token.as_bytes() {
-                [b'<', arg @ .., b'.', b'.', b'>'] if all_alpha(arg) => ArgType {
-                    key: append(arg)?,
-                    kind: Kind::Variadic,
-                },
-                [b'<', arg @ .., b'?', b'>'] if all_alpha(arg) => ArgType {
-                    key: append(arg)?,
-                    kind: Kind::Optional,
-                },
-                [b'<', arg @ .., b'>'] if all_alpha(arg) => ArgType {
-                    key: append(arg)?,
-                    kind: Kind::Required,
-                },
-                // TODO report invalid patterns
-                // TODO report invalid characters in keys
-                _ => continue,
+                    let (inner, kind) = if let Some(inner) = inner.strip_suffix("..") {
+                        (inner, Kind::Variadic)
+                    } else if let Some(inner) = inner.strip_suffix('?') {
+                        (inner, Kind::Optional)
+                    } else {
+                        (inner, Kind::Required)
+                    };
+
+                    match inner.split_once(':') {
+                        Some((key, ty)) => (key, kind, Type::parse(ty)?),
+                        None => (inner, kind, Type::String),
+                    }
+                }
             };
 
-            let done = arg.kind == Kind::Variadic;
-            args.push(arg);
+            anyhow::ensure!(!key.is_empty(), "an empty key in: '{token}'");
+            if let Some(ch) = key
+                .chars()
+                .find(|&c| !matches!(c, 'A'..='Z' | 'a'..='z' | '0'..='9' | '_' | '-'))
+            {
+                anyhow::bail!("invalid character '{ch}' in key: '{key}'")
+            }
+            anyhow::ensure!(seen.insert(key), "{key} was already used");
 
-            if done {
-                break;
+            if kind == Kind::Required {
+                if let Some(ArgType { key: prev, .. }) =
+                    args.iter().find(|a| a.kind == Kind::Optional)
+                {
+                    anyhow::bail!("required <{key}> cannot come after optional <{prev}?>")
+                }
             }
+
+            args.push(ArgType {
+                key: key.into(),
+                kind,
+                ty,
+            });
         }
 
         Ok(Self {
@@ -148,6 +394,7 @@ impl ExampleArgs {
 pub struct ArgType {
     key: String,
     kind: Kind,
+    ty: Type,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -155,4 +402,395 @@ pub enum Kind {
     Required,
     Optional,
     Variadic,
+    Switch,
+    Named,
+}
+
+impl Kind {
+    const fn is_flag(&self) -> bool {
+        matches!(self, Self::Switch | Self::Named)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Segment {
+    start: usize,
+    end: usize,
+    value: String,
+    quoted: bool,
+}
+
+// splits on whitespace, with "double quoted segments" that understand \" and \\
+struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+    // without this, a quote is just another character
+    quotes: bool,
+}
+
+impl<'a> Lexer<'a> {
+    const fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            pos: 0,
+            quotes: true,
+        }
+    }
+
+    fn next_segment(&mut self) -> Result<Option<Segment>, Reason> {
+        let input = self.input;
+        let mut iter = input[self.pos..]
+            .char_indices()
+            .map(|(i, ch)| (self.pos + i, ch))
+            .skip_while(|(_, ch)| ch.is_whitespace())
+            .peekable();
+
+        let (start, ch) = match iter.peek() {
+            Some(&next) => next,
+            None => {
+                self.pos = input.len();
+                return Ok(None);
+            }
+        };
+
+        if ch != '"' || !self.quotes {
+            let end = iter
+                .find(|(_, ch)| ch.is_whitespace())
+                .map_or(input.len(), |(i, _)| i);
+            self.pos = end;
+            return Ok(Some(Segment {
+                start,
+                end,
+                value: input[start..end].to_string(),
+                quoted: false,
+            }));
+        }
+
+        iter.next();
+        let mut value = String::new();
+        let end = loop {
+            match iter.next() {
+                Some((i, '"')) => break i + 1,
+                Some((_, '\\')) => match iter.next() {
+                    Some((_, ch @ ('"' | '\\'))) => value.push(ch),
+                    Some((_, ch)) => value.extend(['\\', ch]),
+                    None => value.push('\\'),
+                },
+                Some((_, ch)) => value.push(ch),
+                None => return Err(Reason::new("args.unclosed-quote")),
+            }
+        };
+
+        self.pos = end;
+        Ok(Some(Segment {
+            start,
+            end,
+            value,
+            quoted: true,
+        }))
+    }
+}
+
+impl Segment {
+    // the original text spanned by the segments, unless a flag was pulled out from between them
+    fn join(input: &str, segments: &[Self], all: &[Self]) -> String {
+        let (head, tail) = match segments {
+            [head, .., tail] => (head, tail),
+            [single] => (single, single),
+            [] => return String::new(),
+        };
+
+        let inside = all
+            .iter()
+            .filter(|s| s.start >= head.start && s.end <= tail.end)
+            .count();
+        if inside == segments.len() {
+            return input[head.start..tail.end].to_string();
+        }
+
+        segments
+            .iter()
+            .map(|s| &input[s.start..s.end])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    String,
+    Number { min: i128, max: i128 },
+    User,
+    Duration,
+    Choice(Box<[Box<str>]>),
+}
+
+impl Type {
+    fn parse(input: &str) -> anyhow::Result<Self> {
+        macro_rules! number {
+            ($($ty:ident)*) => {
+                match input {
+                    $(stringify!($ty) => return Ok(Self::Number {
+                        min: $ty::MIN as _,
+                        max: $ty::MAX as _,
+                    }),)*
+                    _ => {}
+                }
+            };
+        }
+        number!(u8 u16 u32 u64 usize i8 i16 i32 i64 isize);
+
+        let ty = match input {
+            "" => anyhow::bail!("an empty type"),
+            "string" => Self::String,
+            "@user" => Self::User,
+            "duration" => Self::Duration,
+            choices if choices.contains('|') => {
+                let choices = choices
+                    .split('|')
+                    .map(Box::from)
+                    .collect::<Box<[Box<str>]>>();
+                anyhow::ensure!(
+                    choices.iter().all(|c| !c.is_empty()),
+                    "an empty choice in: '{input}'"
+                );
+                Self::Choice(choices)
+            }
+            ty => anyhow::bail!("unknown type: '{ty}'"),
+        };
+        Ok(ty)
+    }
+
+    fn validate(&self, input: &str) -> Option<Value> {
+        match self {
+            Self::String => Some(Value::String(input.to_string())),
+            Self::Number { min, max } => input
+                .parse::<i128>()
+                .ok()
+                .filter(|n| (min..=max).contains(&n))
+                .map(Value::Number),
+            Self::User => {
+                let name = input.strip_prefix('@').unwrap_or(input);
+                (!name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_'))
+                    .then(|| Value::User(name.to_string()))
+            }
+            Self::Duration => parse_duration(input).map(Value::Duration),
+            Self::Choice(choices) => choices
+                .iter()
+                .find(|c| c.eq_ignore_ascii_case(input))
+                .map(|c| Value::Choice(c.to_string())),
+        }
+    }
+
+    fn expected(&self) -> Reason {
+        match self {
+            Self::String => Reason::new("args.expected.string"),
+            Self::Number { .. } => Reason::new("args.expected.number"),
+            Self::User => Reason::new("args.expected.user"),
+            Self::Duration => Reason::new("args.expected.duration"),
+            Self::Choice(choices) => {
+                Reason::new("args.expected.choice").with("choices", choices.join(", "))
+            }
+        }
+    }
+}
+
+// 90, 90s, 5m, 1h30m, 2d
+fn parse_duration(input: &str) -> Option<Duration> {
+    if let Ok(secs) = input.parse() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let mut total = 0_u64;
+    let mut rest = input;
+    while !rest.is_empty() {
+        let pos = rest.find(|c: char| !c.is_ascii_digit())?;
+        let (n, tail) = rest.split_at(pos);
+        let n = n.parse::<u64>().ok()?;
+        let mut chars = tail.chars();
+        let unit = match chars.next()? {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 60 * 60 * 24,
+            _ => return None,
+        };
+        total = total.checked_add(n.checked_mul(unit)?)?;
+        rest = chars.as_str();
+    }
+
+    (total > 0).then_some(Duration::from_secs(total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(usage: &str, input: &str) -> Match<Arguments> {
+        ExampleArgs::parse(usage).unwrap().extract(input)
+    }
+
+    fn args(usage: &str, input: &str) -> Arguments {
+        match extract(usage, input) {
+            Match::Match(args) => args,
+            other => panic!("'{input}' did not match '{usage}': {other:?}"),
+        }
+    }
+
+    fn expected(usage: &str, input: &str) -> String {
+        match extract(usage, input) {
+            Match::Invalid { expected, .. } => expected.tr(&Locale::default()),
+            other => panic!("'{input}' was not invalid for '{usage}': {other:?}"),
+        }
+    }
+
+    #[test]
+    fn number() {
+        let args = args("<count:u8> <offset:i32?>", "200 -5");
+        assert_eq!(args.value("count"), Some(&Value::Number(200)));
+        assert_eq!(args.get_parsed::<u8>("count").unwrap().unwrap(), 200);
+        assert_eq!(args.get_parsed::<i32>("offset").unwrap().unwrap(), -5);
+        assert_eq!(args.get("offset"), Some("-5"));
+
+        // the declared type is checked, not the requested one
+        assert!(args.get_parsed::<u32>("offset").unwrap().is_err());
+        assert!(args.get_parsed::<Duration>("count").unwrap().is_err());
+        assert!(args.get_parsed::<u8>("missing").is_none());
+
+        assert_eq!(expected("<count:u8>", "300"), "a number");
+        assert_eq!(expected("<count:u8>", "-1"), "a number");
+        assert_eq!(expected("<count:u8>", "ten"), "a number");
+    }
+
+    #[test]
+    fn user() {
+        let args = args("<name:@user>", "@museun");
+        assert_eq!(args.value("name"), Some(&Value::User("museun".into())));
+        assert_eq!(args.get("name"), Some("museun"));
+        assert_eq!(
+            args.get_parsed::<String>("name").unwrap().unwrap(),
+            "museun"
+        );
+
+        assert_eq!(
+            self::args("<name:@user>", "shaken_bot").get("name"),
+            Some("shaken_bot")
+        );
+
+        assert_eq!(expected("<name:@user>", "@"), "a user name");
+        assert_eq!(expected("<name:@user>", "not-a-name"), "a user name");
+    }
+
+    #[test]
+    fn duration() {
+        let args = args("<after:duration> --every=duration", "1h30m --every 90");
+        assert_eq!(
+            args.get_duration("after"),
+            Some(Duration::from_secs(90 * 60))
+        );
+        assert_eq!(
+            args.get_parsed::<Duration>("every").unwrap().unwrap(),
+            Duration::from_secs(90)
+        );
+        assert_eq!(args.get("after"), Some("5400"));
+
+        for input in ["1x", "h", "5m30"] {
+            assert_eq!(
+                expected("<after:duration>", input),
+                "a duration (like 1h30m)",
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn choice() {
+        let args = args("<mode:on|off>", "OFF");
+        assert_eq!(args.value("mode"), Some(&Value::Choice("off".into())));
+        assert_eq!(args.get("mode"), Some("off"));
+
+        assert_eq!(expected("<mode:on|off>", "maybe"), "one of: on, off");
+    }
+
+    #[test]
+    fn switch() {
+        let args = args("<name> --exact", "shook --exact");
+        assert!(args.flag("exact"));
+        assert!(args.get_parsed::<bool>("exact").unwrap().unwrap());
+
+        let args = self::args("<name> --exact", "shook");
+        assert!(!args.flag("exact"));
+        assert!(!args.flag("name"));
+    }
+
+    #[test]
+    fn quotes() {
+        let args = args("<name> <target?>", r#""hello world" "say \"hi\"""#);
+        assert_eq!(args.get("name"), Some("hello world"));
+        assert_eq!(args.get("target"), Some(r#"say "hi""#));
+
+        // a quoted flag is just text
+        let args = self::args("<name> --exact", r#""--exact""#);
+        assert_eq!(args.get("name"), Some("--exact"));
+        assert!(!args.flag("exact"));
+    }
+
+    #[test]
+    fn variadic_is_taken_as_written() {
+        let usage = "<name> <body..>";
+        let args = args(usage, r#"!x he said "hi"#);
+        assert_eq!(args.get("name"), Some("!x"));
+        assert_eq!(args.get("body"), Some(r#"he said "hi"#));
+
+        let args = self::args(usage, r#""!x" "just a quote""#);
+        assert_eq!(args.get("name"), Some("!x"));
+        assert_eq!(args.get("body"), Some(r#""just a quote""#));
+
+        let args = self::args("<body..>", "  spaced   out  ");
+        assert_eq!(args.get("body"), Some("spaced   out"));
+    }
+
+    #[test]
+    fn flags() {
+        let usage = "<name> <body..> --exact --limit=u8";
+        let args = args(usage, "--limit 3 foo some --exact text");
+        assert_eq!(args.get("name"), Some("foo"));
+        assert_eq!(args.get("body"), Some("some text"));
+        assert!(args.flag("exact"));
+        assert_eq!(args.get_parsed::<u8>("limit").unwrap().unwrap(), 3);
+
+        let args = self::args(usage, "foo --limit=4 -- --exact is text");
+        assert_eq!(args.get("body"), Some("--exact is text"));
+        assert!(!args.flag("exact"));
+
+        // without declared flags, dashes are just text
+        let args = self::args("<body..>", "--exact -- text");
+        assert_eq!(args.get("body"), Some("--exact -- text"));
+
+        assert!(matches!(
+            extract(usage, "foo --nope"),
+            Match::Malformed { .. }
+        ));
+        assert!(matches!(
+            extract(usage, "foo --exact=yes"),
+            Match::Malformed { .. }
+        ));
+        assert_eq!(expected(usage, "foo --limit"), "a number");
+    }
+
+    #[test]
+    fn unbalanced() {
+        assert!(matches!(
+            extract("<name> <target?>", r#""hello world"#),
+            Match::Malformed { .. }
+        ));
+        assert!(matches!(
+            extract("<name> <body..>", r#""!x body"#),
+            Match::Malformed { .. }
+        ));
+
+        // the free text is never lexed
+        let args = args("<name> <body..>", r#"!x "body"#);
+        assert_eq!(args.get("body"), Some(r#""body"#));
+    }
 }
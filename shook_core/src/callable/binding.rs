@@ -1,10 +1,19 @@
-use std::{future::Future, sync::Arc};
+use std::{future::Future, sync::Arc, time::Duration};
 
-use super::{Command, Dispatch, IntoCallable, SharedCallable};
+use super::{
+    dispatch::{AbortOnDrop, Sequence},
+    group::{Group, Handler, Subcommand},
+    middleware::layer_shared,
+    Command, IntoCallable, Middleware, Permission, SharedCallable, Throttle,
+};
 use crate::{
+    help::Description,
+    prefix,
     prelude::{Message, SharedRegistry},
     render::Render,
+    schedule::{self, Schedule},
     state::GlobalState,
+    BoxedFuture,
 };
 
 fn command_name<A, B, C>(f: impl Fn(A, B) -> C + Copy) -> String {
@@ -22,23 +31,88 @@ fn command_name<A, B, C>(f: impl Fn(A, B) -> C + Copy) -> String {
     v.join("::")
 }
 
+// this is the same as the first half of `command_name` for a method on `T`
+fn namespace<T>() -> String {
+    use heck::ToSnekCase as _;
+
+    let name = std::any::type_name::<T>();
+    name.rsplit("::").next().unwrap_or(name).to_snek_case()
+}
+
+// the registry can make a command stricter than the code asks for, but never looser
+fn at_least(mut cmd: Command, permission: Permission) -> Command {
+    cmd.permission = cmd.permission.max(permission);
+    cmd
+}
+
+// the handler reads its arguments by the keys in the declared usage, so a different usage
+// in the registry is ignored rather than trusted
+fn override_with(mut cmd: Command, desc: &Description) -> Command {
+    let declared = cmd.example.as_ref().map(|example| &*example.usage);
+    if desc.usage.is_some() && desc.usage.as_deref() != declared {
+        log::warn!(
+            "ignoring the registry usage for {}, it is declared as '{}'",
+            cmd.command,
+            declared.unwrap_or_default()
+        );
+    }
+
+    cmd.description = Some(desc.description.as_str().into());
+    if !desc.aliases.is_empty() {
+        cmd.aliases = desc.aliases.iter().map(|alias| (&**alias).into()).collect();
+    }
+    if desc.cooldown.is_some() {
+        cmd.cooldown = desc.cooldown.clone();
+    }
+    cmd.permission = cmd.permission.max(desc.permission);
+
+    cmd.subcommands = std::mem::take(&mut cmd.subcommands)
+        .into_iter()
+        .map(
+            |sub| match desc.subcommands.iter().find(|d| *d.command == *sub.command) {
+                Some(desc) => override_with(sub, desc),
+                None => sub,
+            },
+        )
+        .collect();
+    cmd
+}
+
 pub struct Binding<T> {
     this: Arc<T>,
+    namespace: String,
     callables: Vec<SharedCallable>,
+    groups: Vec<(Command, Vec<Subcommand>)>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    timers: Vec<BoxedFuture<'static, ()>>,
     registry: SharedRegistry,
+    state: GlobalState,
 }
 
 impl<T> IntoCallable for Binding<T>
 where
     T: Send + Sync + 'static,
 {
-    fn into_callable(self) -> SharedCallable {
-        let callables = Arc::new(self.callables);
-        let func = move |msg| {
-            let callables = Arc::clone(&callables);
-            async move { Dispatch::new(&callables).into_render(&msg).await.boxed() }
-        };
-        Arc::new(func)
+    fn into_callable(mut self) -> SharedCallable {
+        self.callables.extend(
+            self.groups
+                .into_iter()
+                .map(|(parent, subs)| Arc::new(Group::new(parent, subs)) as SharedCallable),
+        );
+        // timers start once the binding is finished, and stop once it is dropped
+        let timers = self
+            .timers
+            .into_iter()
+            .map(|timer| AbortOnDrop(tokio::spawn(timer)))
+            .collect();
+        let sequence: SharedCallable =
+            Arc::new(Sequence::named(self.namespace, self.callables).with_timers(timers));
+
+        // the first layer added is the outermost one
+        self.middleware
+            .into_iter()
+            .rev()
+            .fold(sequence, layer_shared)
     }
 }
 
@@ -49,33 +123,82 @@ where
     pub async fn create(state: GlobalState, this: T) -> Self {
         Self {
             this: Arc::new(this),
+            namespace: namespace::<T>(),
             callables: Vec::new(),
-            registry: state.get_owned().await,
+            groups: Vec::new(),
+            middleware: Vec::new(),
+            timers: Vec::new(),
+            registry: state.try_get_owned().unwrap_or_else(|| {
+                log::warn!("there is no help registry");
+                SharedRegistry::default()
+            }),
+            state,
         }
     }
 
+    // middleware wraps every command and listener in this binding
+    pub fn layer(mut self, middleware: impl Middleware) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
     pub fn bind<F, Fut>(self, func: F) -> Self
+    where
+        F: Fn(Arc<T>, Message) -> Fut + Copy + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Render + Send + 'static,
+    {
+        self.bind_restricted(Permission::Everyone, func)
+    }
+
+    // nobody below `permission` can use this, even if the registry entry has no permission
+    pub fn bind_restricted<F, Fut>(self, permission: Permission, func: F) -> Self
     where
         F: Fn(Arc<T>, Message) -> Fut + Copy + Send + Sync + 'static,
         Fut: Future + Send,
         Fut::Output: Render + Send + 'static,
     {
         let id = command_name(func);
-        let cmd = self.registry.fetch(&id);
-        self.bind_cmd(cmd, func)
+        let cmd = self.registry.fetch(&id).unwrap_or_else(|err| {
+            // this is reported by `Registry::check_bindings`, so it is still bound under its own name
+            log::warn!("{err:#}");
+            let name = id.rsplit("::").next().unwrap_or(&id);
+            Command::new(&format!("{}{name}", prefix::MARKER))
+        });
+        self.bind_exact(at_least(cmd, permission), func)
     }
 
-    pub fn bind_cmd<F, Fut>(mut self, cmd: Command, func: F) -> Self
+    // a registry entry with the same name can change the help, aliases, cooldown and permission of `cmd`,
+    // so a command from `#[command]` can be adjusted without a rebuild. its usage always comes from `cmd`
+    pub fn bind_cmd<F, Fut>(self, cmd: Command, func: F) -> Self
     where
         F: Fn(Arc<T>, Message) -> Fut + Copy + Send + Sync + 'static,
         Fut: Future + Send,
         Fut::Output: Render + Send + 'static,
     {
+        let cmd = match self.registry.find_command(&cmd.command) {
+            Some(desc) => override_with(cmd, desc),
+            None => cmd,
+        };
+        self.bind_exact(cmd, func)
+    }
+
+    fn bind_exact<F, Fut>(mut self, cmd: Command, func: F) -> Self
+    where
+        F: Fn(Arc<T>, Message) -> Fut + Copy + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Render + Send + 'static,
+    {
+        if !cmd.subcommands.is_empty() {
+            return self.bind_subcommands(cmd, func);
+        }
+
         let cmd = Arc::new(cmd);
+        let throttle = Arc::new(Throttle::default());
         let func = (cmd.clone(), {
             let this = self.this.clone();
             move |msg: Message| {
-                super::wrap(msg, cmd.clone(), {
+                super::wrap(msg, cmd.clone(), throttle.clone(), {
                     let this = this.clone();
                     move |msg| {
                         let this = this.clone();
@@ -89,6 +212,82 @@ where
         self
     }
 
+    // every subcommand of `cmd` is bound to `func`, commands with the same parent share a group
+    fn bind_subcommands<F, Fut>(mut self, mut cmd: Command, func: F) -> Self
+    where
+        F: Fn(Arc<T>, Message) -> Fut + Copy + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Render + Send + 'static,
+    {
+        let func: Handler = {
+            let this = self.this.clone();
+            Arc::new(move |msg| {
+                let this = this.clone();
+                Box::pin(async move { func(this, msg).await.boxed() })
+            })
+        };
+
+        let subcommands = std::mem::take(&mut cmd.subcommands);
+        let pos = match self
+            .groups
+            .iter()
+            .position(|(parent, _)| parent.command == cmd.command)
+        {
+            Some(pos) => pos,
+            None => {
+                self.groups.push((cmd.clone(), Vec::new()));
+                self.groups.len() - 1
+            }
+        };
+
+        let (parent, subs) = &mut self.groups[pos];
+        for alias in cmd.aliases {
+            if !parent.aliases.contains(&alias) {
+                parent.aliases.push(alias)
+            }
+        }
+        for sub in subcommands {
+            parent.subcommands.push(sub.clone());
+            subs.push(Subcommand {
+                cmd: Arc::new(sub),
+                throttle: Arc::new(Throttle::default()),
+                func: func.clone(),
+            });
+        }
+        self
+    }
+
+    // the output is announced on each transport, the first run happens after one period
+    pub fn every<F, Fut>(self, period: Duration, func: F) -> Self
+    where
+        F: Fn(Arc<T>, GlobalState) -> Fut + Copy + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Render + Send + 'static,
+    {
+        self.schedule(Schedule::every(period), func)
+    }
+
+    pub fn schedule<F, Fut>(mut self, schedule: Schedule, func: F) -> Self
+    where
+        F: Fn(Arc<T>, GlobalState) -> Fut + Copy + Send + Sync + 'static,
+        Fut: Future + Send,
+        Fut::Output: Render + Send + 'static,
+    {
+        let tick = {
+            let (this, state) = (self.this.clone(), self.state.clone());
+            move || {
+                let (this, state) = (this.clone(), state.clone());
+                Box::pin(async move { func(this, state).await.boxed() }) as BoxedFuture<'_, _>
+            }
+        };
+
+        let name = command_name(func);
+        let state = self.state.clone();
+        self.timers
+            .push(Box::pin(schedule::run(name, schedule, state, tick)));
+        self
+    }
+
     pub fn listen<F, Fut>(mut self, func: F) -> Self
     where
         F: Fn(Arc<T>, Message) -> Fut + Copy + Send + Sync + 'static,
@@ -2,12 +2,17 @@ use std::sync::Arc;
 
 use crate::args::ExampleArgs;
 
+use super::{Cooldown, Permission};
+
 #[derive(Clone, Debug)]
 pub struct Command {
     pub command: Arc<str>,
     pub aliases: Vec<Arc<str>>,
     pub description: Option<Arc<str>>,
     pub example: Option<Arc<ExampleArgs>>,
+    pub permission: Permission,
+    pub cooldown: Option<Cooldown>,
+    pub subcommands: Vec<Command>,
 }
 
 impl Command {
@@ -17,6 +22,9 @@ impl Command {
             aliases: Vec::new(),
             description: None,
             example: None,
+            permission: Permission::Everyone,
+            cooldown: None,
+            subcommands: Vec::new(),
         }
     }
 
@@ -35,4 +43,27 @@ impl Command {
         self.description.get_or_insert_with(|| Arc::from(help));
         self
     }
+
+    pub fn permission(mut self, permission: Permission) -> Self {
+        self.permission = permission;
+        self
+    }
+
+    pub fn cooldown(mut self, cooldown: Cooldown) -> Self {
+        self.cooldown.replace(cooldown);
+        self
+    }
+
+    // subcommands are matched against the first word after this command
+    pub fn subcommand(mut self, subcommand: Command) -> Self {
+        self.subcommands.push(subcommand);
+        self
+    }
+
+    pub fn matches_subcommand(&self, input: &str) -> bool {
+        [&*self.command]
+            .into_iter()
+            .chain(self.aliases.iter().map(|c| &**c))
+            .any(|c| c == input)
+    }
 }
@@ -0,0 +1,297 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::Permission;
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Cooldown {
+    // these are all in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub global: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<u64>,
+    pub bypass: Permission,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub notify: bool,
+}
+
+impl Default for Cooldown {
+    fn default() -> Self {
+        Self {
+            global: None,
+            user: None,
+            channel: None,
+            bypass: Permission::Moderator,
+            notify: false,
+        }
+    }
+}
+
+impl Cooldown {
+    pub fn global(secs: u64) -> Self {
+        Self {
+            global: Some(secs),
+            ..Self::default()
+        }
+    }
+
+    pub fn user(secs: u64) -> Self {
+        Self {
+            user: Some(secs),
+            ..Self::default()
+        }
+    }
+
+    pub fn channel(secs: u64) -> Self {
+        Self {
+            channel: Some(secs),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.global.is_none() && self.user.is_none() && self.channel.is_none()
+    }
+
+    fn scopes(&self, user: &str, channel: &str) -> impl Iterator<Item = (Scope, Duration)> {
+        [
+            (Scope::Global, self.global),
+            (Scope::User(user.to_string()), self.user),
+            (Scope::Channel(channel.to_string()), self.channel),
+        ]
+        .into_iter()
+        .filter_map(|(scope, secs)| secs.map(|secs| (scope, Duration::from_secs(secs))))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Scope {
+    Global,
+    User(String),
+    Channel(String),
+}
+
+#[derive(Debug, Default)]
+pub struct Throttle {
+    last: Mutex<HashMap<Scope, Instant>>,
+}
+
+impl Throttle {
+    // the global scope counts as used at `now`, so nothing gets through until its cooldown has passed
+    pub fn started_at(now: Instant) -> Self {
+        Self {
+            last: Mutex::new(HashMap::from([(Scope::Global, now)])),
+        }
+    }
+
+    // on success, this marks every scope of the cooldown as used at `now`.
+    // otherwise, it returns how long is left on the longest scope
+    pub fn try_acquire(
+        &self,
+        cooldown: &Cooldown,
+        now: Instant,
+        user: &str,
+        channel: &str,
+    ) -> Result<(), Duration> {
+        let mut last = self.last.lock().unwrap();
+
+        let remaining = cooldown
+            .scopes(user, channel)
+            .filter_map(|(scope, dur)| {
+                let then = last.get(&scope)?;
+                (*then + dur).checked_duration_since(now)
+            })
+            .filter(|left| !left.is_zero())
+            .max();
+
+        if let Some(remaining) = remaining {
+            return Err(remaining);
+        }
+
+        // anything older than the longest window can't hold up a caller anymore, so it is dropped
+        if let Some(longest) = cooldown.scopes(user, channel).map(|(_, dur)| dur).max() {
+            last.retain(|_, then| now.saturating_duration_since(*then) < longest);
+        }
+
+        last.extend(
+            cooldown
+                .scopes(user, channel)
+                .map(|(scope, _)| (scope, now)),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        callable::{Binding, Command, IntoCallable},
+        prelude::{GlobalState, Message, Response, SharedCallable},
+        testing::{Mock as _, MockMessage, TestBinding},
+    };
+
+    struct Ping;
+
+    impl Ping {
+        async fn ping(self: std::sync::Arc<Self>, _: Message) -> &'static str {
+            "pong"
+        }
+    }
+
+    // the sender and channel change between messages, which the builder methods on `TestBinding` take by value
+    struct Pinger(Option<TestBinding>);
+
+    impl Pinger {
+        async fn new(cooldown: Cooldown) -> Self {
+            Self::bind(Command::new("!ping").cooldown(cooldown)).await
+        }
+
+        async fn bind(cmd: Command) -> Self {
+            let bind = move |state: GlobalState| {
+                let cmd = cmd.clone();
+                async move {
+                    anyhow::Result::<SharedCallable>::Ok(
+                        Binding::create(state, Ping)
+                            .await
+                            .bind_cmd(cmd, Ping::ping)
+                            .into_callable(),
+                    )
+                }
+            };
+            Self(Some(bind.mock().await))
+        }
+
+        fn with_moderator(mut self) -> Self {
+            self.0 = self.0.map(TestBinding::with_moderator);
+            self
+        }
+
+        async fn ping(&mut self, sender: &str, channel: &str) -> bool {
+            self.send("!ping", sender, channel).await
+        }
+
+        async fn send(&mut self, input: &str, sender: &str, channel: &str) -> bool {
+            let mut binding = self
+                .0
+                .take()
+                .unwrap()
+                .with_sender(sender)
+                .with_channel(channel);
+            binding.send_message::<MockMessage>(input).await;
+            let pong = binding.get_response() == [Response::Say(String::from("pong"))];
+            self.0 = Some(binding);
+            pong
+        }
+
+        fn advance_time(&self, dur: Duration) {
+            self.0.as_ref().unwrap().advance_time(dur)
+        }
+    }
+
+    #[tokio::test]
+    async fn global() {
+        let mut binding = Pinger::new(Cooldown::global(10)).await;
+        assert!(binding.ping("a", "#a").await);
+        assert!(!binding.ping("b", "#b").await);
+
+        binding.advance_time(Duration::from_secs(10));
+        assert!(binding.ping("b", "#b").await);
+    }
+
+    #[tokio::test]
+    async fn user() {
+        let mut binding = Pinger::new(Cooldown::user(10)).await;
+        assert!(binding.ping("a", "#a").await);
+        assert!(!binding.ping("a", "#b").await);
+        assert!(binding.ping("b", "#a").await);
+
+        binding.advance_time(Duration::from_secs(10));
+        assert!(binding.ping("a", "#a").await);
+    }
+
+    #[tokio::test]
+    async fn channel() {
+        let mut binding = Pinger::new(Cooldown::channel(10)).await;
+        assert!(binding.ping("a", "#a").await);
+        assert!(!binding.ping("b", "#a").await);
+        assert!(binding.ping("a", "#b").await);
+
+        binding.advance_time(Duration::from_secs(10));
+        assert!(binding.ping("b", "#a").await);
+    }
+
+    #[tokio::test]
+    async fn bypass() {
+        let mut binding = Pinger::new(Cooldown::global(10)).await.with_moderator();
+        assert!(binding.ping("a", "#a").await);
+        assert!(binding.ping("a", "#a").await);
+    }
+
+    #[tokio::test]
+    async fn parent_of_subcommands() {
+        let cmd = Command::new("!ping")
+            .cooldown(Cooldown::user(10))
+            .subcommand(Command::new("a"))
+            .subcommand(Command::new("b").cooldown(Cooldown::user(30)));
+        let mut binding = Pinger::bind(cmd).await;
+
+        assert!(binding.send("!ping a", "a", "#a").await);
+        assert!(!binding.send("!ping b", "a", "#a").await);
+        assert!(binding.send("!ping b", "b", "#a").await);
+
+        binding.advance_time(Duration::from_secs(10));
+        assert!(binding.send("!ping a", "a", "#a").await);
+        assert!(!binding.send("!ping b", "b", "#a").await);
+    }
+
+    // a subcommand that cannot be used doesn't start the parent's cooldown
+    #[tokio::test]
+    async fn parent_is_charged_by_use() {
+        let cmd = Command::new("!ping")
+            .cooldown(Cooldown::user(10))
+            .subcommand(Command::new("a"))
+            .subcommand(Command::new("count").usage("<n:u32>").unwrap())
+            .subcommand(Command::new("mod").permission(Permission::Moderator));
+        let mut binding = Pinger::bind(cmd).await;
+
+        assert!(!binding.send("!ping count many", "a", "#a").await);
+        assert!(!binding.send("!ping mod", "a", "#a").await);
+        assert!(!binding.send("!ping missing", "a", "#a").await);
+        assert!(binding.send("!ping a", "a", "#a").await);
+        assert!(!binding.send("!ping a", "a", "#a").await);
+    }
+
+    #[test]
+    fn old_entries_are_dropped() {
+        let throttle = Throttle::default();
+        let cooldown = Cooldown::user(10);
+        let now = Instant::now();
+
+        for user in ["a", "b", "c"] {
+            throttle.try_acquire(&cooldown, now, user, "#a").unwrap();
+        }
+        assert_eq!(throttle.last.lock().unwrap().len(), 3);
+
+        let later = now + Duration::from_secs(10);
+        throttle.try_acquire(&cooldown, later, "d", "#a").unwrap();
+        assert_eq!(throttle.last.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn started_at() {
+        let now = Instant::now();
+        let throttle = Throttle::started_at(now);
+        let cooldown = Cooldown::global(60);
+
+        assert!(throttle.try_acquire(&cooldown, now, "a", "#a").is_err());
+        let later = now + Duration::from_secs(60);
+        assert!(throttle.try_acquire(&cooldown, later, "a", "#a").is_ok());
+    }
+}
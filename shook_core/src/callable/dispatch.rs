@@ -1,47 +1,288 @@
-use std::future::Future;
+use std::{any::Any, future::Future, sync::Arc, time::Duration};
 
-use tokio_stream::{Stream, StreamExt};
+use tokio::{
+    task::{JoinError, JoinHandle},
+    time::Instant,
+};
 
 use crate::{
     prelude::Message,
-    render::{BoxedRender, Render},
+    render::{BoxedRender, Render, Response},
+    BoxedFuture,
 };
 
-use super::CallableFn;
+use super::{CallableFn, Command, SharedCallable};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DispatchPolicy {
+    // every matching command runs
+    All,
+    // only the first matching command runs, listeners still run
+    #[default]
+    FirstMatch,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct DispatchConfig {
+    pub policy: DispatchPolicy,
+    // this is in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<u64>,
+    // this sends a problem to chat when a callable panics or times out
+    pub report_failures: bool,
+    // long responses are split, and anything past this is left out
+    pub max_messages: usize,
+}
+
+impl Default for DispatchConfig {
+    fn default() -> Self {
+        Self {
+            policy: DispatchPolicy::default(),
+            deadline: Some(30),
+            report_failures: true,
+            max_messages: 5,
+        }
+    }
+}
 
+// responses are collected in the order of `seq`, so earlier callables have priority
 pub struct Dispatch<'a, C> {
     seq: &'a [C],
+    policy: DispatchPolicy,
+    deadline: Option<Duration>,
+    report_failures: bool,
+    nested: bool,
 }
 
+// the outermost dispatch puts this on the message, so nested dispatches hold each of their callables to it
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Guard {
+    // this is when the callables have to be done, and how long they were given
+    deadline: Option<(Instant, Duration)>,
+    report_failures: bool,
+}
+
+// the outermost dispatch waits this much longer, so a nested dispatch can report its own timeouts
+const BACKSTOP: Duration = Duration::from_millis(100);
+
 impl<'a, C> Dispatch<'a, C>
 where
     C: CallableFn + Clone,
     <<C as CallableFn>::Out as Future>::Output: Render,
 {
     pub const fn new(seq: &'a [C]) -> Self {
-        Self { seq }
+        Self {
+            seq,
+            policy: DispatchPolicy::All,
+            deadline: None,
+            report_failures: false,
+            nested: false,
+        }
+    }
+
+    pub fn with_config(self, config: &DispatchConfig) -> Self {
+        let this = self
+            .with_policy(config.policy)
+            .report_failures(config.report_failures);
+        match config.deadline {
+            Some(secs) => this.with_deadline(Duration::from_secs(secs)),
+            None => this,
+        }
+    }
+
+    pub const fn with_policy(mut self, policy: DispatchPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub const fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub const fn report_failures(mut self, report_failures: bool) -> Self {
+        self.report_failures = report_failures;
+        self
+    }
+
+    // a dispatch inside of a callable uses the deadline of the dispatch it is in
+    pub const fn nested(mut self) -> Self {
+        self.nested = true;
+        self
     }
 
     pub async fn into_render(self, msg: &Message) -> impl Render {
-        let mut stream = self.dispatch(msg);
+        let mut msg = msg.clone();
+        if let DispatchPolicy::FirstMatch = self.policy {
+            if let Some(cmd) = self.seq.iter().find_map(|c| c.command_for(&msg)) {
+                msg.select_command(cmd);
+            }
+        }
+
+        let (guard, deadline) = match msg.guard() {
+            Some(guard) if self.nested => (guard, guard.deadline),
+            _ => {
+                let deadline = self
+                    .deadline
+                    .map(|deadline| (Instant::now() + deadline, deadline));
+                let guard = Guard {
+                    deadline,
+                    report_failures: self.report_failures,
+                };
+                msg.set_guard(guard);
+                (
+                    guard,
+                    deadline.map(|(at, deadline)| (at + BACKSTOP, deadline)),
+                )
+            }
+        };
+
+        let handles = self
+            .seq
+            .iter()
+            .map(|callable| {
+                let name = callable
+                    .command_for(&msg)
+                    .map(|cmd| msg.display_command(&cmd.command));
+                let handle = tokio::spawn({
+                    let (callable, msg) = (callable.clone(), msg.clone());
+                    async move { callable.call(msg).await.boxed() }
+                });
+                (name, AbortOnDrop(handle))
+            })
+            .collect::<Vec<_>>();
+
         let mut out = vec![];
-        while let Some(el) = stream.next().await {
-            out.push(el);
+        for (name, mut handle) in handles {
+            let result = match deadline {
+                Some((at, deadline)) => tokio::time::timeout_at(at, &mut handle.0)
+                    .await
+                    .map_err(|_| Failure::Timeout(deadline))
+                    .and_then(|result| result.map_err(Failure::from)),
+                None => (&mut handle.0).await.map_err(Failure::from),
+            };
+
+            match result {
+                Ok(render) => out.push(render),
+                Err(failure) => {
+                    let name = name.unwrap_or_else(|| msg.tr("dispatch.listener"));
+                    log::error!("{name} failed on '{}': {failure}", msg.data());
+                    if guard.report_failures {
+                        let problem = msg.tr_with(failure.reason_key(), &[("name", &name)]);
+                        out.push(Response::Problem(problem).boxed())
+                    }
+                }
+            }
         }
         out
     }
+}
+
+// this stops the task when its owner is dropped, or gives up on it
+pub(super) struct AbortOnDrop<T>(pub(super) JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort()
+    }
+}
 
-    fn dispatch(self, msg: &Message) -> impl Stream<Item = BoxedRender> {
-        let (tx, rx) = tokio::sync::mpsc::channel(std::cmp::max(1, self.seq.len()));
-        for callable in self.seq.iter().map(Clone::clone) {
-            let tx = tx.clone();
-            let msg = msg.clone();
-            let _ = tokio::spawn(async move {
-                let render = callable.call(msg).await;
-                let _ = tx.send(render.boxed()).await;
-            });
+enum Failure {
+    Timeout(Duration),
+    Panic(Box<dyn Any + Send>),
+    Cancelled,
+}
+
+impl Failure {
+    const fn reason_key(&self) -> &'static str {
+        match self {
+            Self::Timeout(..) => "dispatch.timeout",
+            Self::Panic(..) => "dispatch.panic",
+            Self::Cancelled => "dispatch.cancelled",
+        }
+    }
+}
+
+impl From<JoinError> for Failure {
+    fn from(err: JoinError) -> Self {
+        if !err.is_panic() {
+            return Self::Cancelled;
+        }
+
+        Self::Panic(err.into_panic())
+    }
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Timeout(deadline) => write!(f, "timed out after {deadline:?}"),
+            Self::Panic(panic) => {
+                let msg = panic
+                    .downcast_ref::<&str>()
+                    .copied()
+                    .or_else(|| panic.downcast_ref::<String>().map(|s| &**s))
+                    .unwrap_or("unknown panic");
+                write!(f, "panicked: {msg}")
+            }
+            Self::Cancelled => f.write_str("was cancelled"),
+        }
+    }
+}
+
+// this runs a sequence of callables as a single callable
+pub(super) struct Sequence {
+    seq: Arc<[SharedCallable]>,
+    namespace: Option<String>,
+    timers: Vec<AbortOnDrop<()>>,
+}
+
+impl Sequence {
+    pub(super) fn new(seq: impl Into<Arc<[SharedCallable]>>) -> Self {
+        Self {
+            seq: seq.into(),
+            namespace: None,
+            timers: Vec::new(),
         }
-        drop(tx);
-        tokio_stream::wrappers::ReceiverStream::new(rx)
+    }
+
+    // a binding's commands are reported under `namespace`
+    pub(super) fn named(namespace: String, seq: impl Into<Arc<[SharedCallable]>>) -> Self {
+        Self {
+            namespace: Some(namespace),
+            ..Self::new(seq)
+        }
+    }
+
+    // the timers of a binding run for as long as its sequence is around
+    pub(super) fn with_timers(mut self, timers: Vec<AbortOnDrop<()>>) -> Self {
+        self.timers = timers;
+        self
+    }
+}
+
+impl CallableFn for Sequence {
+    type Out = BoxedFuture<'static, BoxedRender>;
+
+    fn call(&self, msg: Message) -> Self::Out {
+        let seq = Arc::clone(&self.seq);
+        Box::pin(async move { Dispatch::new(&seq).nested().into_render(&msg).await.boxed() })
+    }
+
+    fn all_commands(&self) -> Vec<&Command> {
+        self.seq.iter().flat_map(|c| c.all_commands()).collect()
+    }
+
+    fn namespaces(&self) -> Vec<(&str, Vec<&Command>)> {
+        match &self.namespace {
+            Some(namespace) => vec![(namespace, self.all_commands())],
+            None => self.seq.iter().flat_map(|c| c.namespaces()).collect(),
+        }
+    }
+
+    fn command_for(&self, msg: &Message) -> Option<Arc<Command>> {
+        self.seq.iter().find_map(|c| c.command_for(msg))
     }
 }
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use super::{CallableFn, Command, Throttle};
+use crate::{prelude::Message, render::BoxedRender, BoxedFuture};
+
+pub(super) type Handler = Arc<dyn Fn(Message) -> BoxedFuture<'static, BoxedRender> + Send + Sync>;
+
+#[derive(Clone)]
+pub(super) struct Subcommand {
+    pub(super) cmd: Arc<Command>,
+    pub(super) throttle: Arc<Throttle>,
+    pub(super) func: Handler,
+}
+
+// a parent command which dispatches to one of its subcommands
+pub(super) struct Group {
+    parent: Arc<Command>,
+    throttle: Arc<Throttle>,
+    subcommands: Arc<[Subcommand]>,
+}
+
+impl Group {
+    pub(super) fn new(parent: Command, subcommands: Vec<Subcommand>) -> Self {
+        Self {
+            parent: Arc::new(parent),
+            throttle: Arc::new(Throttle::default()),
+            subcommands: subcommands.into(),
+        }
+    }
+}
+
+impl CallableFn for Group {
+    type Out = BoxedFuture<'static, BoxedRender>;
+
+    fn call(&self, msg: Message) -> Self::Out {
+        Box::pin(super::wrap_group(
+            msg,
+            self.parent.clone(),
+            self.throttle.clone(),
+            self.subcommands.clone(),
+        ))
+    }
+
+    fn usage(&self) -> Option<&str> {
+        Some(&self.parent.command)
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.parent.description.as_deref()
+    }
+
+    fn all_commands(&self) -> Vec<&Command> {
+        vec![&self.parent]
+    }
+
+    fn command_for(&self, msg: &Message) -> Option<Arc<Command>> {
+        super::wrap::check_command(&self.parent, msg).then(|| self.parent.clone())
+    }
+}
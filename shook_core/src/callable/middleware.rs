@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use super::{dispatch::Sequence, CallableFn, Command, IntoCallable, SharedCallable};
+use crate::{
+    prelude::Message,
+    render::{BoxedRender, Render, RenderFlavor, Response},
+    BoxedFuture,
+};
+
+pub enum Flow {
+    Continue,
+    // this skips the wrapped callable, and uses this render instead
+    Stop(BoxedRender),
+}
+
+impl Flow {
+    pub fn stop(render: impl Render + 'static) -> Self {
+        Self::Stop(render.boxed())
+    }
+}
+
+#[async_trait::async_trait]
+pub trait Middleware
+where
+    Self: Send + Sync + 'static,
+{
+    async fn before(&self, _msg: &Message) -> Flow {
+        Flow::Continue
+    }
+
+    // this runs when the responses are rendered, so it sees the final flavor
+    fn after(&self, _msg: &Message, _flavor: RenderFlavor, _responses: &mut Vec<Response>) {}
+}
+
+// this wraps `inner` so `middleware` sees every message before it, and every response after it
+pub fn layer(inner: SharedCallable, middleware: impl Middleware) -> SharedCallable {
+    layer_shared(inner, Arc::new(middleware))
+}
+
+pub(super) fn layer_shared(
+    inner: SharedCallable,
+    middleware: Arc<dyn Middleware>,
+) -> SharedCallable {
+    Arc::new(Layered { inner, middleware })
+}
+
+struct Layered {
+    inner: SharedCallable,
+    middleware: Arc<dyn Middleware>,
+}
+
+impl CallableFn for Layered {
+    type Out = BoxedFuture<'static, BoxedRender>;
+
+    fn call(&self, msg: Message) -> Self::Out {
+        let inner = self.inner.clone();
+        let middleware = self.middleware.clone();
+        Box::pin(async move {
+            if let Flow::Stop(render) = middleware.before(&msg).await {
+                return render;
+            }
+
+            let render = inner.call(msg.clone()).await;
+            After {
+                render,
+                middleware,
+                msg,
+            }
+            .boxed()
+        })
+    }
+
+    fn all_commands(&self) -> Vec<&Command> {
+        self.inner.all_commands()
+    }
+
+    fn namespaces(&self) -> Vec<(&str, Vec<&Command>)> {
+        self.inner.namespaces()
+    }
+
+    fn usage(&self) -> Option<&str> {
+        self.inner.usage()
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.inner.description()
+    }
+
+    fn command_for(&self, msg: &Message) -> Option<Arc<Command>> {
+        self.inner.command_for(msg)
+    }
+}
+
+struct After {
+    render: BoxedRender,
+    middleware: Arc<dyn Middleware>,
+    msg: Message,
+}
+
+impl Render for After {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        let mut responses = self.render.render(flavor);
+        self.middleware.after(&self.msg, flavor, &mut responses);
+        responses
+    }
+}
+
+// this lets a whole set of callables be layered as one
+impl<const N: usize> IntoCallable for [SharedCallable; N] {
+    fn into_callable(self) -> SharedCallable {
+        Arc::new(Sequence::new(self))
+    }
+}
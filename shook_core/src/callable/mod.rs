@@ -4,11 +4,19 @@ use crate::{message::Message, render::BoxedRender, render::Render, BoxedFuture};
 
 mod binding;
 mod command;
+mod cooldown;
 mod dispatch;
+mod group;
+mod middleware;
+mod permission;
 
 pub use binding::Binding;
 pub use command::Command;
-pub use dispatch::Dispatch;
+pub use cooldown::{Cooldown, Throttle};
+pub(crate) use dispatch::Guard;
+pub use dispatch::{Dispatch, DispatchConfig, DispatchPolicy};
+pub use middleware::{layer, Flow, Middleware};
+pub use permission::Permission;
 
 pub trait IntoCallable {
     fn into_callable(self) -> SharedCallable;
@@ -26,12 +34,21 @@ where
     fn all_commands(&self) -> Vec<&Command> {
         vec![]
     }
+    // the commands of each binding, by the namespace it was bound under
+    fn namespaces(&self) -> Vec<(&str, Vec<&Command>)> {
+        vec![]
+    }
     fn usage(&self) -> Option<&str> {
         None
     }
     fn description(&self) -> Option<&str> {
         None
     }
+
+    // this is the command which would handle `msg`, listeners never have one
+    fn command_for(&self, _msg: &Message) -> Option<Arc<Command>> {
+        None
+    }
 }
 
 impl<F> CallableFn for Arc<F>
@@ -49,6 +66,11 @@ where
         (**self).all_commands()
     }
 
+    #[inline]
+    fn namespaces(&self) -> Vec<(&str, Vec<&Command>)> {
+        (**self).namespaces()
+    }
+
     #[inline]
     fn usage(&self) -> Option<&str> {
         (**self).usage()
@@ -58,6 +80,11 @@ where
     fn description(&self) -> Option<&str> {
         (**self).description()
     }
+
+    #[inline]
+    fn command_for(&self, msg: &Message) -> Option<Arc<Command>> {
+        (**self).command_for(msg)
+    }
 }
 
 impl<F, Fut> CallableFn for F
@@ -99,7 +126,11 @@ where
     fn all_commands(&self) -> Vec<&Command> {
         vec![&self.0]
     }
+
+    fn command_for(&self, msg: &Message) -> Option<Arc<Command>> {
+        wrap::check_command(&self.0, msg).then(|| self.0.clone())
+    }
 }
 
 mod wrap;
-pub(self) use wrap::wrap;
+use wrap::{wrap, wrap_group};
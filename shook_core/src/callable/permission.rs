@@ -0,0 +1,53 @@
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Permission {
+    #[default]
+    Everyone,
+    Subscriber,
+    Vip,
+    Moderator,
+    Broadcaster,
+    BotOwner,
+}
+
+impl Permission {
+    pub fn is_everyone(&self) -> bool {
+        matches!(self, Self::Everyone)
+    }
+
+    // levels are ordered, so a moderator can do everything a vip can do
+    pub fn satisfies(&self, required: Self) -> bool {
+        *self >= required
+    }
+
+    // this is looked up in the catalog, e.g. `permission.moderator`
+    pub fn requirement_key(&self) -> String {
+        format!("permission.{self}")
+    }
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Everyone => "everyone",
+            Self::Subscriber => "subscriber",
+            Self::Vip => "vip",
+            Self::Moderator => "moderator",
+            Self::Broadcaster => "broadcaster",
+            Self::BotOwner => "bot-owner",
+        };
+        f.write_str(s)
+    }
+}
@@ -1,69 +1,216 @@
 use std::{future::Future, sync::Arc};
 
 use crate::{
-    args::{Arguments, Match},
-    prelude::Message,
-    render::{BoxedRender, Render, RenderFlavor, Response},
+    args::{Arguments, Match, Reason},
+    prelude::{Locale, Message},
+    render::{BoxedRender, Render, Response},
+    FormatTime,
 };
 
-use super::Command;
+use super::{group::Subcommand, Command, Permission, Throttle};
 
 pub async fn wrap<F>(
-    mut msg: Message,
+    msg: Message,
     cmd: Arc<Command>,
+    throttle: Arc<Throttle>,
     func: impl Fn(Message) -> F + Send + Sync,
 ) -> BoxedRender
 where
     F: Future + Send,
     F::Output: Render + Send + 'static,
 {
-    enum MatchError {
-        Required { usage: String },
-        NoMatch { usage: String },
+    if !check_command(&cmd, &msg) || !msg.is_selected(&cmd) {
+        return ().boxed();
     }
 
-    impl Render for MatchError {
-        fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
-            // TODO make this better for discord
-            let data = match self {
-                Self::Required { usage } => format!("an argument is required: {usage}"),
-                Self::NoMatch { usage } => format!("invalid arguments: {usage}"),
-            };
-            data.render(flavor)
-        }
+    let permission = msg.permission();
+    let input = msg.input().to_string();
+    let target = Target {
+        cmd: &cmd,
+        label: &cmd.command,
+        throttle: &throttle,
+        parent: None,
+    };
+    invoke(msg, target, permission, &input, func).await
+}
+
+pub async fn wrap_group(
+    msg: Message,
+    parent: Arc<Command>,
+    throttle: Arc<Throttle>,
+    subcommands: Arc<[Subcommand]>,
+) -> BoxedRender {
+    if !check_command(&parent, &msg) || !msg.is_selected(&parent) {
+        return ().boxed();
     }
 
-    fn check_command(cmd: &Command, msg: &Message) -> bool {
-        [&*cmd.command]
-            .into_iter()
-            .chain(cmd.aliases.iter().map(|c| &**c))
-            .fold(false, |ok, c| ok ^ msg.match_command(c))
+    let permission = msg.permission();
+    if !permission.satisfies(parent.permission) {
+        let problem = msg.tr(&parent.permission.requirement_key());
+        return Response::Problem(problem).boxed();
     }
 
-    if !check_command(&cmd, &msg) {
-        return ().boxed();
+    let usage = || {
+        let names = subcommands
+            .iter()
+            .filter(|sub| permission.satisfies(sub.cmd.permission))
+            .map(|sub| &*sub.cmd.command)
+            .collect::<Vec<_>>();
+        format!("{} <{}>", parent.command, names.join("|"))
+    };
+
+    let input = msg.input().to_string();
+    let (name, rest) = input
+        .split_once(char::is_whitespace)
+        .map(|(name, rest)| (name, rest.trim_start()))
+        .unwrap_or((&*input, ""));
+
+    if name.is_empty() {
+        return MatchError::Required { usage: usage() }.respond(&msg);
     }
 
-    // TODO just do split_at
-    let head = std::cmp::min(msg.command().len() + 1, msg.data().len());
-    let input = &msg.data()[head..];
+    let sub = match subcommands
+        .iter()
+        .find(|sub| sub.cmd.matches_subcommand(name))
+    {
+        Some(sub) => sub,
+        None => return MatchError::NoMatch { usage: usage() }.respond(&msg),
+    };
+
+    let label = format!("{} {}", parent.command, sub.cmd.command);
+    let target = Target {
+        cmd: &sub.cmd,
+        label: &label,
+        throttle: &sub.throttle,
+        parent: Some((&parent, &throttle)),
+    };
+    invoke(msg, target, permission, rest, &*sub.func).await
+}
+
+struct Target<'a> {
+    cmd: &'a Command,
+    label: &'a str,
+    throttle: &'a Throttle,
+    // the parent's cooldown is shared by all of its subcommands
+    parent: Option<(&'a Command, &'a Throttle)>,
+}
+
+async fn invoke<F>(
+    mut msg: Message,
+    Target {
+        cmd,
+        label,
+        throttle,
+        parent,
+    }: Target<'_>,
+    permission: Permission,
+    input: &str,
+    func: impl Fn(Message) -> F + Send + Sync,
+) -> BoxedRender
+where
+    F: Future + Send,
+    F::Output: Render + Send + 'static,
+{
+    if !permission.satisfies(cmd.permission) {
+        let problem = msg.tr(&cmd.permission.requirement_key());
+        return Response::Problem(problem).boxed();
+    }
 
     if let Some(example) = &cmd.example {
         let args = match example.extract(input) {
             Match::Required => {
-                let usage = cmd.command.to_string();
-                return MatchError::Required { usage }.boxed();
+                let usage = label.to_string();
+                return MatchError::Required { usage }.respond(&msg);
             }
 
             Match::NoMatch => {
-                let usage = cmd.command.to_string();
-                return MatchError::NoMatch { usage }.boxed();
+                let usage = label.to_string();
+                return MatchError::NoMatch { usage }.respond(&msg);
+            }
+            Match::Invalid { key, expected } => {
+                return MatchError::Invalid { key, expected }.respond(&msg);
             }
-            Match::Match(map) => Arguments { map },
+            Match::Malformed { reason } => {
+                return MatchError::Malformed { reason }.respond(&msg);
+            }
+            Match::Match(args) => args,
             Match::Exact => Arguments::default(),
         };
         msg.get_args().replace(args);
     };
 
+    // nothing is charged until the command is actually going to be used
+    let shared =
+        parent.and_then(|(parent, throttle)| check_cooldown(&msg, parent, throttle, permission));
+    if let Some(resp) = shared.or_else(|| check_cooldown(&msg, cmd, throttle, permission)) {
+        return resp;
+    }
+
     func(msg).await.boxed()
 }
+
+fn check_cooldown(
+    msg: &Message,
+    cmd: &Command,
+    throttle: &Throttle,
+    permission: Permission,
+) -> Option<BoxedRender> {
+    let cooldown = cmd.cooldown.as_ref()?;
+    if permission.satisfies(cooldown.bypass) {
+        return None;
+    }
+
+    let now = msg.state().clock().now();
+    let left = throttle
+        .try_acquire(cooldown, now, msg.sender_name(), msg.source())
+        .err()?;
+    if !cooldown.notify {
+        return Some(().boxed());
+    }
+
+    let left = std::cmp::max(left, std::time::Duration::from_secs(1));
+    let left = left.as_readable_time();
+    let data = msg.tr_with("cooldown", &[("left", &left)]);
+    Some(Response::Reply(data).boxed())
+}
+
+pub(super) fn check_command(cmd: &Command, msg: &Message) -> bool {
+    [&*cmd.command]
+        .into_iter()
+        .chain(cmd.aliases.iter().map(|c| &**c))
+        .fold(false, |ok, c| ok ^ msg.match_command(c))
+}
+
+enum MatchError {
+    Required { usage: String },
+    NoMatch { usage: String },
+    Invalid { key: String, expected: Reason },
+    Malformed { reason: Reason },
+}
+
+impl MatchError {
+    // TODO make this better for discord
+    fn format(&self, locale: &Locale) -> String {
+        match self {
+            Self::Required { usage } => locale.tr_with("args.required", &[("usage", usage)]),
+            Self::NoMatch { usage } => locale.tr_with("args.no-match", &[("usage", usage)]),
+            Self::Invalid { key, expected } => {
+                let id = if key.starts_with("--") {
+                    "args.invalid-flag"
+                } else {
+                    "args.invalid"
+                };
+                let expected = expected.tr(locale);
+                locale.tr_with(id, &[("key", key), ("expected", &expected)])
+            }
+            Self::Malformed { reason } => {
+                let reason = reason.tr(locale);
+                locale.tr_with("args.malformed", &[("reason", &reason)])
+            }
+        }
+    }
+
+    fn respond(self, msg: &Message) -> BoxedRender {
+        self.format(&msg.locale()).boxed()
+    }
+}
@@ -0,0 +1,20 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+// the wall clock, plus an offset that tests can push forward
+#[derive(Clone, Debug, Default)]
+pub struct Clock {
+    offset: Arc<Mutex<Duration>>,
+}
+
+impl Clock {
+    pub fn now(&self) -> Instant {
+        Instant::now() + *self.offset.lock().unwrap()
+    }
+
+    pub fn advance(&self, dur: Duration) {
+        *self.offset.lock().unwrap() += dur;
+    }
+}
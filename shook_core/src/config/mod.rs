@@ -0,0 +1,57 @@
+use crate::{state::GlobalState, BoxedFuture};
+use std::path::Path;
+
+mod sqlite;
+
+mod storage;
+pub use storage::Storage;
+
+pub trait ConfigPath {
+    fn file_path(&self) -> &Path;
+}
+
+pub trait PersistFromConfig:
+    serde::Serialize + for<'de> serde::Deserialize<'de> + Send + Sync + 'static
+{
+    type ConfigPath: ConfigPath + Send + Sync + 'static;
+
+    // a database can hold more than one thing, so each type is stored under its own key.
+    // this is also the name of the file it is imported from, e.g. `user_defined.json`
+    const KEY: &'static str;
+
+    fn storage(state: &GlobalState) -> anyhow::Result<Storage> {
+        Storage::for_path(state.get_config_path::<Self::ConfigPath>()?)
+    }
+
+    fn save_to_file<'a>(&'a self, state: &'a GlobalState) -> BoxedFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move { Self::storage(state)?.save(Self::KEY, self).await })
+    }
+
+    fn load_from_file(state: &GlobalState) -> BoxedFuture<'_, anyhow::Result<Self>>
+    where
+        Self: Sized,
+    {
+        Box::pin(async move { Self::storage(state)?.load(Self::KEY).await })
+    }
+
+    // this loads, changes and saves the value. with sqlite, no other writer can get in between.
+    // the saved value is returned, along with whatever `update` returned
+    fn update_file<F, R>(
+        state: &GlobalState,
+        update: F,
+    ) -> BoxedFuture<'_, anyhow::Result<(Self, R)>>
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        Box::pin(async move { Self::storage(state)?.update(Self::KEY, update).await })
+    }
+}
+
+async fn blocking<T>(func: impl FnOnce() -> anyhow::Result<T> + Send + 'static) -> anyhow::Result<T>
+where
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(func).await?
+}
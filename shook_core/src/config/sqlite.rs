@@ -0,0 +1,314 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Context;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, TransactionBehavior};
+
+use shook_config::SafeFile;
+
+use super::{blocking, Storage};
+
+// each entry is a step, and `user_version` is how many of them have run
+const MIGRATIONS: &[&str] = &["CREATE TABLE documents (
+    key        TEXT PRIMARY KEY NOT NULL,
+    data       TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+)"];
+
+// these are looked for next to the database, as `<key>.<ext>`
+const LEGACY_EXTENSIONS: &[&str] = &["json", "yaml", "yml", "toml"];
+
+pub(super) async fn load<T>(path: PathBuf, key: String) -> anyhow::Result<T>
+where
+    T: serde::Serialize + for<'de> serde::Deserialize<'de> + Send + Sync + 'static,
+{
+    // a corrupted database falls back to its newest good backup
+    let data = blocking({
+        let (path, key) = (path.clone(), key.clone());
+        move || {
+            SafeFile::new(&path).load(|file| {
+                // a backup is only read, migrating it would change it
+                let conn = if file == path {
+                    open(file)?
+                } else {
+                    open_read_only(file)?
+                };
+                read(&conn, &key)
+            })
+        }
+    })
+    .await?;
+
+    let data = match data {
+        Some(data) => data,
+        None => import::<T>(path, &key).await?,
+    };
+    decode(&key, &data)
+}
+
+pub(super) async fn save(path: PathBuf, key: String, data: String) -> anyhow::Result<()> {
+    blocking(move || {
+        let conn = open(&path)?;
+        write(&conn, &key, &data)?;
+        backup(&conn, &path);
+        Ok(())
+    })
+    .await
+}
+
+pub(super) async fn update<T, F, R>(path: PathBuf, key: String, update: F) -> anyhow::Result<(T, R)>
+where
+    T: serde::Serialize + for<'de> serde::Deserialize<'de> + Send + Sync + 'static,
+    F: FnOnce(&mut T) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    // this makes sure an older file has been imported first
+    load::<T>(path.clone(), key.clone()).await?;
+
+    blocking(move || {
+        let mut conn = open(&path)?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let data = read(&tx, &key)?.with_context(|| missing(&path, &key))?;
+
+        let mut val = decode(&key, &data)?;
+        let out = update(&mut val);
+        write(&tx, &key, &serde_json::to_string(&val)?)?;
+
+        tx.commit()?;
+        backup(&conn, &path);
+        Ok((val, out))
+    })
+    .await
+}
+
+// this runs once per key, when the database doesn't have it yet. the old file is renamed so it isn't used again
+async fn import<T>(path: PathBuf, key: &str) -> anyhow::Result<String>
+where
+    T: serde::Serialize + for<'de> serde::Deserialize<'de> + Send + Sync + 'static,
+{
+    let legacy = LEGACY_EXTENSIONS
+        .iter()
+        .map(|ext| path.with_file_name(format!("{key}.{ext}")))
+        .find(|legacy| legacy.is_file())
+        .with_context(|| missing(&path, key))?;
+
+    log::info!("importing {} into {}", legacy.display(), path.display());
+    let val: T = Storage::for_path(&legacy)?.load_file().await?;
+    let data = serde_json::to_string(&val)?;
+
+    // another task could have imported it while this was loading the file
+    let data = blocking({
+        let key = key.to_string();
+        move || {
+            let mut conn = open(&path)?;
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let data = match read(&tx, &key)? {
+                Some(existing) => existing,
+                None => {
+                    write(&tx, &key, &data)?;
+                    data
+                }
+            };
+            tx.commit()?;
+            Ok(data)
+        }
+    })
+    .await?;
+
+    let mut migrated = legacy.clone().into_os_string();
+    migrated.push(".migrated");
+    if let Err(err) = tokio::fs::rename(&legacy, &migrated).await {
+        log::warn!("cannot rename {}: {err}", legacy.display())
+    }
+    Ok(data)
+}
+
+fn open(path: &Path) -> anyhow::Result<Connection> {
+    let mut conn =
+        Connection::open(path).with_context(|| format!("cannot open {}", path.display()))?;
+    // another writer has this long to finish before this gives up
+    conn.busy_timeout(Duration::from_secs(5))?;
+    // a rollback journal leaves nothing next to the database between transactions, so a backup can replace it
+    conn.pragma_update_and_check(None, "journal_mode", "DELETE", |_| Ok(()))?;
+    migrate(&mut conn)?;
+    Ok(conn)
+}
+
+fn open_read_only(path: &Path) -> anyhow::Result<Connection> {
+    let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    Connection::open_with_flags(path, flags)
+        .with_context(|| format!("cannot open {}", path.display()))
+}
+
+fn migrate(conn: &mut Connection) -> anyhow::Result<()> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let version: i64 = tx.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    let pending = MIGRATIONS.iter().enumerate().skip(version as usize);
+    for (i, migration) in pending {
+        log::debug!("running migration {} on the database", i + 1);
+        tx.execute_batch(migration)
+            .with_context(|| format!("cannot run migration {}", i + 1))?;
+    }
+
+    tx.pragma_update(None, "user_version", MIGRATIONS.len() as i64)?;
+    tx.commit()?;
+    Ok(())
+}
+
+// this copies the database after each write, keeping the newest few
+fn backup(conn: &Connection, path: &Path) {
+    let file = SafeFile::new(path);
+    let backup = file.next_backup();
+    if backup.exists() {
+        return;
+    }
+
+    if let Err(err) = conn.execute("VACUUM INTO ?1", [&*backup.to_string_lossy()]) {
+        log::warn!("cannot back up {}: {err}", path.display())
+    }
+    file.prune();
+}
+
+fn read(conn: &Connection, key: &str) -> anyhow::Result<Option<String>> {
+    Ok(conn
+        .query_row("SELECT data FROM documents WHERE key = ?1", [key], |row| {
+            row.get(0)
+        })
+        .optional()?)
+}
+
+fn write(conn: &Connection, key: &str, data: &str) -> anyhow::Result<()> {
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    conn.execute(
+        "INSERT INTO documents (key, data, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT (key) DO UPDATE SET data = excluded.data, updated_at = excluded.updated_at",
+        rusqlite::params![key, data, now],
+    )?;
+    Ok(())
+}
+
+fn decode<T>(key: &str, data: &str) -> anyhow::Result<T>
+where
+    T: for<'de> serde::Deserialize<'de>,
+{
+    serde_json::from_str(data).with_context(|| format!("'{key}' is invalid"))
+}
+
+fn missing(path: &Path, key: &str) -> String {
+    format!("cannot find '{key}' in {}", path.display())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, Default, PartialEq, Eq)]
+    struct Doc {
+        names: Vec<String>,
+    }
+
+    fn doc(names: &[&str]) -> Doc {
+        Doc {
+            names: names.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    fn migrated(path: &Path) -> PathBuf {
+        let mut migrated = path.to_path_buf().into_os_string();
+        migrated.push(".migrated");
+        migrated.into()
+    }
+
+    #[tokio::test]
+    async fn import_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("shook.db");
+        let legacy = dir.path().join("user_defined.json");
+        std::fs::write(&legacy, r#"{"names": ["a", "b"]}"#).unwrap();
+
+        let val: Doc = load(db.clone(), "user_defined".into()).await.unwrap();
+        assert_eq!(val, doc(&["a", "b"]));
+        assert!(!legacy.exists());
+        assert!(migrated(&legacy).exists());
+
+        // the database has it now
+        let val: Doc = load(db, "user_defined".into()).await.unwrap();
+        assert_eq!(val, doc(&["a", "b"]));
+    }
+
+    #[tokio::test]
+    async fn import_yaml() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("shook.db");
+
+        for (key, ext) in [("filter_patterns", "yaml"), ("other_patterns", "yml")] {
+            let legacy = dir.path().join(format!("{key}.{ext}"));
+            std::fs::write(&legacy, "names:\n  - c\n").unwrap();
+
+            let val: Doc = load(db.clone(), key.into()).await.unwrap();
+            assert_eq!(val, doc(&["c"]), "{ext}");
+            assert!(migrated(&legacy).exists(), "{ext}");
+        }
+    }
+
+    #[tokio::test]
+    async fn missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("shook.db");
+        let err = load::<Doc>(db, "user_defined".into()).await.unwrap_err();
+        assert!(
+            err.to_string().contains("cannot find 'user_defined'"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn update_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("shook.db");
+        std::fs::write(dir.path().join("doc.json"), r#"{"names": ["a"]}"#).unwrap();
+
+        let (val, len) = update(db.clone(), "doc".into(), |doc: &mut Doc| {
+            doc.names.push(String::from("b"));
+            doc.names.len()
+        })
+        .await
+        .unwrap();
+        assert_eq!((val, len), (doc(&["a", "b"]), 2));
+
+        save(db.clone(), "other".into(), String::from(r#"{"names": []}"#))
+            .await
+            .unwrap();
+
+        // each key is left alone by the others
+        let val: Doc = load(db.clone(), "doc".into()).await.unwrap();
+        assert_eq!(val, doc(&["a", "b"]));
+        let val: Doc = load(db, "other".into()).await.unwrap();
+        assert_eq!(val, Doc::default());
+    }
+
+    #[tokio::test]
+    async fn load_from_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("shook.db");
+
+        for names in [r#"{"names": ["a"]}"#, r#"{"names": ["b"]}"#] {
+            // backups are named by the millisecond
+            std::thread::sleep(Duration::from_millis(2));
+            save(db.clone(), "doc".into(), names.into()).await.unwrap();
+        }
+
+        let backup = SafeFile::new(&db).backups().remove(0);
+        let before = std::fs::read(&backup).unwrap();
+
+        std::fs::write(&db, "not a database").unwrap();
+        let val: Doc = load(db, "doc".into()).await.unwrap();
+        assert_eq!(val, doc(&["b"]));
+
+        // reading the backup did not write to it
+        assert_eq!(std::fs::read(&backup).unwrap(), before);
+    }
+}
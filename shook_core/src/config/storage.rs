@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+
+use shook_config::SafeFile;
+
+use super::{blocking, sqlite};
+
+// this is picked by the file extension
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Storage {
+    Json(PathBuf),
+    Yaml(PathBuf),
+    Toml(PathBuf),
+    // one database can hold many values, each is stored under its key
+    Sqlite(PathBuf),
+}
+
+impl Storage {
+    pub fn for_path(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        let ext = match path.extension() {
+            Some(ext) => ext.to_string_lossy().to_ascii_lowercase(),
+            None => anyhow::bail!("cannot find config file for: {}", path.to_string_lossy()),
+        };
+
+        Ok(match &*ext {
+            "json" => Self::Json(path),
+            "yaml" | "yml" => Self::Yaml(path),
+            "toml" => Self::Toml(path),
+            "db" | "sqlite" | "sqlite3" => Self::Sqlite(path),
+            ext => anyhow::bail!("invalid file extension: {ext}"),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Json(path) | Self::Yaml(path) | Self::Toml(path) | Self::Sqlite(path) => path,
+        }
+    }
+
+    pub async fn load<T>(&self, key: &str) -> anyhow::Result<T>
+    where
+        T: serde::Serialize + for<'de> serde::Deserialize<'de> + Send + Sync + 'static,
+    {
+        match self {
+            Self::Sqlite(path) => sqlite::load(path.clone(), key.to_string()).await,
+            _ => self.load_file().await,
+        }
+    }
+
+    // sqlite imports older files with this
+    pub(super) async fn load_file<T>(&self) -> anyhow::Result<T>
+    where
+        T: serde::Serialize + for<'de> serde::Deserialize<'de> + Send + Sync + 'static,
+    {
+        let this = self.clone();
+        blocking(move || {
+            SafeFile::new(this.path()).load(|path| {
+                let data = std::fs::read(path)?;
+                this.decode(&data)
+            })
+        })
+        .await
+    }
+
+    pub async fn save<T>(&self, key: &str, val: &T) -> anyhow::Result<()>
+    where
+        T: serde::Serialize + for<'de> serde::Deserialize<'de> + Send + Sync + 'static,
+    {
+        let data = match self {
+            Self::Json(..) => serde_json::to_vec_pretty(val)?,
+            Self::Yaml(..) => serde_yaml::to_string(val)?.into_bytes(),
+            // toml wants plain values before tables, which its `Value` takes care of
+            Self::Toml(..) => toml::to_string_pretty(&toml::Value::try_from(val)?)?.into_bytes(),
+            Self::Sqlite(path) => {
+                let data = serde_json::to_string(val)?;
+                return sqlite::save(path.clone(), key.to_string(), data).await;
+            }
+        };
+
+        let path = self.path().to_path_buf();
+        blocking(move || SafeFile::new(path).write_bytes(&data)).await
+    }
+
+    // sqlite does this in one transaction, so writers from other tasks (or processes) wait their turn.
+    // the files are just loaded and saved again
+    pub async fn update<T, F, R>(&self, key: &str, update: F) -> anyhow::Result<(T, R)>
+    where
+        T: serde::Serialize + for<'de> serde::Deserialize<'de> + Send + Sync + 'static,
+        F: FnOnce(&mut T) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        if let Self::Sqlite(path) = self {
+            return sqlite::update(path.clone(), key.to_string(), update).await;
+        }
+
+        let mut val = self.load(key).await?;
+        let out = update(&mut val);
+        self.save(key, &val).await?;
+        Ok((val, out))
+    }
+
+    // these are sorted newest first
+    pub fn backups(&self) -> Vec<PathBuf> {
+        SafeFile::new(self.path()).backups()
+    }
+
+    // this replaces the file with a backup, the newest one if `backup` is `None`
+    pub async fn restore(&self, backup: Option<PathBuf>) -> anyhow::Result<PathBuf> {
+        let path = self.path().to_path_buf();
+        blocking(move || SafeFile::new(path).restore(backup.as_deref())).await
+    }
+
+    fn decode<T>(&self, data: &[u8]) -> anyhow::Result<T>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        match self {
+            Self::Json(..) => Ok(serde_json::from_slice(data)?),
+            Self::Yaml(..) => Ok(serde_yaml::from_slice(data)?),
+            Self::Toml(..) => Ok(toml::from_slice(data)?),
+            Self::Sqlite(path) => anyhow::bail!("{} is not a file format", path.display()),
+        }
+    }
+}
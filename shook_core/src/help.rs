@@ -1,6 +1,15 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+};
 
-use crate::prelude::Command;
+use anyhow::Context as _;
+
+use crate::{
+    callable::{Cooldown, Permission},
+    prefix,
+    prelude::{Command, SharedCallable},
+};
 
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct Registry {
@@ -13,15 +22,83 @@ impl Registry {
         self.map.get(namespace)
     }
 
-    pub fn fetch(&self, id: &str) -> Command {
-        let (head, tail) = id.split_once("::").expect("invalid command id");
-        let tail = tail
-            .starts_with('!')
-            .then_some(Cow::from(tail))
-            .unwrap_or_else(|| Cow::from(format!("!{tail}")));
+    pub fn fetch(&self, id: &str) -> anyhow::Result<Command> {
+        let (head, tail) = id
+            .split_once("::")
+            .with_context(|| format!("invalid command id: {id}"))?;
+        let tail = if tail.starts_with(prefix::MARKER) {
+            Cow::from(tail)
+        } else {
+            Cow::from(format!("{}{tail}", prefix::MARKER))
+        };
         self.get_command(head, &tail)
-            .unwrap_or_else(|| panic!("'{id}' was invalid"))
-            .unwrap()
+            .or_else(|| self.get_subcommand(head, prefix::strip_marker(&tail)))
+            .with_context(|| format!("'{id}' is not in the registry"))?
+            .with_context(|| format!("'{id}' is invalid"))
+    }
+
+    // this is what was actually bound, so `!help` only lists commands that can be used
+    pub fn from_bindings(callables: &[SharedCallable]) -> Self {
+        let mut this = Self::default();
+        for (namespace, commands) in callables.iter().flat_map(|c| c.namespaces()) {
+            for cmd in commands {
+                this.add(namespace, cmd)
+            }
+        }
+        this
+    }
+
+    // this compares the registry with what was bound
+    pub fn check_bindings(&self, callables: &[SharedCallable]) -> BindingReport {
+        let bound = callables
+            .iter()
+            .flat_map(|c| c.namespaces())
+            .flat_map(|(namespace, commands)| commands.into_iter().map(move |cmd| (namespace, cmd)))
+            .collect::<Vec<_>>();
+
+        let mut report = BindingReport::default();
+
+        let known = bound
+            .iter()
+            .map(|(namespace, cmd)| (*namespace, &*cmd.command))
+            .collect::<HashSet<_>>();
+        for (namespace, desc) in &self.map {
+            report.unbound.extend(
+                desc.descriptions
+                    .iter()
+                    .filter(|desc| !known.contains(&(&**namespace, &*desc.command)))
+                    .map(|desc| format!("{namespace}::{}", desc.command)),
+            );
+        }
+
+        let mut names = BTreeMap::<&str, Vec<String>>::new();
+        for (namespace, cmd) in &bound {
+            let id = format!("{namespace}::{}", cmd.command);
+            if self
+                .descriptions_for(namespace)
+                .and_then(|desc| desc.get(&cmd.command))
+                .is_none()
+            {
+                report.unregistered.push(id.clone());
+            }
+
+            let mut seen = HashSet::new();
+            for name in std::iter::once(&cmd.command).chain(&cmd.aliases) {
+                if seen.insert(name) {
+                    names.entry(name).or_default().push(id.clone());
+                }
+            }
+        }
+
+        report.duplicates = names
+            .into_iter()
+            .filter(|(_, ids)| ids.len() > 1)
+            .map(|(name, ids)| format!("{name} is used by {}", ids.join(", ")))
+            .collect();
+
+        report.unbound.sort();
+        report.unregistered.sort();
+        report
     }
 
     pub fn get_command(&self, namespace: &str, cmd: &str) -> Option<anyhow::Result<Command>> {
@@ -30,14 +107,60 @@ impl Registry {
             .map(Description::parse_command)
     }
 
+    // this finds the parent which declares `name` as a subcommand, and returns
+    // the parent with only that subcommand attached
+    pub fn get_subcommand(&self, namespace: &str, name: &str) -> Option<anyhow::Result<Command>> {
+        self.descriptions_for(namespace)?
+            .descriptions
+            .iter()
+            .find_map(|desc| desc.parse_subcommand(name))
+    }
+
+    // this parses every usage pattern, so bad entries are caught when the registry is loaded
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let errors = self
+            .map
+            .iter()
+            .flat_map(|(namespace, desc)| {
+                desc.descriptions.iter().filter_map(move |desc| {
+                    let err = desc.parse_command().err()?;
+                    Some(format!("{namespace}::{}: {err}", desc.command))
+                })
+            })
+            .collect::<Vec<_>>();
+
+        anyhow::ensure!(
+            errors.is_empty(),
+            "invalid registry entries:\n{}",
+            errors.join("\n")
+        );
+        Ok(())
+    }
+
     pub fn get_all_descriptions(&self) -> impl Iterator<Item = &Descriptions> {
         self.map.values()
     }
 
+    // these are sorted by namespace
+    pub fn namespaces(&self) -> Vec<(&str, &Descriptions)> {
+        let mut namespaces = self
+            .map
+            .iter()
+            .map(|(namespace, desc)| (&**namespace, desc))
+            .collect::<Vec<_>>();
+        namespaces.sort_unstable_by_key(|(namespace, _)| *namespace);
+        namespaces
+    }
+
     pub fn find_command(&self, cmd: &str) -> Option<&Description> {
         self.map.values().find_map(|desc| desc.get(cmd))
     }
 
+    pub fn find_command_for(&self, cmd: &str, permission: Permission) -> Option<&Description> {
+        self.find_command(cmd)
+            .filter(|desc| permission.satisfies(desc.permission))
+    }
+
     pub fn add(&mut self, namespace: &str, desc: impl Into<Description>) {
         self.map
             .entry(namespace.to_string())
@@ -46,6 +169,47 @@ impl Registry {
     }
 }
 
+// everything is collected, rather than stopping at the first problem
+#[derive(Debug, Default)]
+pub struct BindingReport {
+    // registry entries without a binding
+    pub unbound: Vec<String>,
+    // bindings without a registry entry
+    pub unregistered: Vec<String>,
+    // names and aliases that more than one command answers to
+    pub duplicates: Vec<String>,
+}
+
+impl BindingReport {
+    pub fn is_empty(&self) -> bool {
+        self.unbound.is_empty() && self.unregistered.is_empty() && self.duplicates.is_empty()
+    }
+
+    // only one of the commands sharing a name would ever run
+    pub fn has_conflicts(&self) -> bool {
+        !self.duplicates.is_empty()
+    }
+}
+
+impl std::fmt::Display for BindingReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (header, list) in [
+            ("registry entries without a binding", &self.unbound),
+            ("bindings without a registry entry", &self.unregistered),
+            ("duplicate commands", &self.duplicates),
+        ] {
+            if list.is_empty() {
+                continue;
+            }
+            writeln!(f, "{header}:")?;
+            for item in list {
+                writeln!(f, "  {item}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 #[serde(transparent)]
 pub struct Descriptions {
@@ -66,10 +230,21 @@ impl Descriptions {
         self.descriptions.push(desc)
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &Description> {
+        self.descriptions.iter()
+    }
+
     pub fn command_names(&self) -> impl Iterator<Item = &str> {
         self.descriptions.iter().flat_map(Description::commands)
     }
 
+    pub fn command_names_for(&self, permission: Permission) -> impl Iterator<Item = &str> {
+        self.descriptions
+            .iter()
+            .filter(move |desc| permission.satisfies(desc.permission))
+            .flat_map(Description::commands)
+    }
+
     pub fn description_for(&self, name: &str) -> Option<&str> {
         self.get(name).map(Description::description)
     }
@@ -87,17 +262,47 @@ pub struct Description {
     pub description: String,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub aliases: Vec<String>,
+    #[serde(skip_serializing_if = "Permission::is_everyone", default)]
+    pub permission: Permission,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub cooldown: Option<Cooldown>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub subcommands: Vec<Description>,
 }
 
 impl Description {
     pub fn parse_command(&self) -> anyhow::Result<Command> {
+        self.subcommands
+            .iter()
+            .try_fold(self.parse_self()?, |cmd, sub| {
+                Ok(cmd.subcommand(sub.parse_self()?))
+            })
+    }
+
+    pub fn parse_subcommand(&self, name: &str) -> Option<anyhow::Result<Command>> {
+        let sub = self
+            .subcommands
+            .iter()
+            .find(|sub| sub.commands().any(|cmd| cmd == name))?;
+        let cmd = self
+            .parse_self()
+            .and_then(|cmd| sub.parse_self().map(|sub| cmd.subcommand(sub)));
+        Some(cmd)
+    }
+
+    fn parse_self(&self) -> anyhow::Result<Command> {
         let mut cmd = self.aliases.iter().fold(
-            Command::new(&self.command).help(&self.description),
+            Command::new(&self.command)
+                .help(&self.description)
+                .permission(self.permission),
             |cmd, a| cmd.alias(a),
         );
         if let Some(usage) = &self.usage {
             cmd = cmd.usage(usage)?;
         }
+        if let Some(cooldown) = &self.cooldown {
+            cmd = cmd.cooldown(cooldown.clone());
+        }
         Ok(cmd)
     }
 
@@ -119,9 +324,23 @@ impl Description {
         let cmd = &self.command;
         match &self.usage {
             Some(usage) => Cow::from(format!("{cmd} {usage}")),
+            None if !self.subcommands.is_empty() => {
+                let names = self
+                    .subcommands
+                    .iter()
+                    .map(|sub| &*sub.command)
+                    .collect::<Vec<_>>();
+                Cow::from(format!("{cmd} <{}>", names.join("|")))
+            }
             None => Cow::from(cmd),
         }
     }
+
+    pub fn subcommands_for(&self, permission: Permission) -> impl Iterator<Item = &Description> {
+        self.subcommands
+            .iter()
+            .filter(move |sub| permission.satisfies(sub.permission))
+    }
 }
 
 impl<'a> From<&'a Command> for Description {
@@ -136,6 +355,9 @@ impl<'a> From<&'a Command> for Description {
                 .or_else(|| cmd.example.as_ref().map(|c| c.usage.to_string()))
                 .unwrap_or_else(|| cmd.command.to_string()),
             usage: cmd.example.as_ref().map(|c| c.usage.to_string()),
+            permission: cmd.permission,
+            cooldown: cmd.cooldown.clone(),
+            subcommands: cmd.subcommands.iter().map(Into::into).collect(),
         }
     }
 }
@@ -145,3 +367,46 @@ impl From<Command> for Description {
         (&cmd).into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> Registry {
+        let mut registry = Registry::default();
+        registry.add(
+            "builtin",
+            Command::new("!theme")
+                .alias("!t")
+                .subcommand(Command::new("set").alias("change"))
+                .subcommand(Command::new("reset")),
+        );
+        registry
+    }
+
+    #[test]
+    fn subcommand_aliases() {
+        let registry = registry();
+        for name in ["set", "change"] {
+            let cmd = registry.get_subcommand("builtin", name).unwrap().unwrap();
+            assert_eq!(&*cmd.command, "!theme");
+            assert_eq!(&*cmd.subcommands[0].command, "set", "{name}");
+        }
+        assert!(registry.get_subcommand("builtin", "unset").is_none());
+    }
+
+    #[test]
+    fn fetch() {
+        let registry = registry();
+        for id in ["builtin::!theme", "builtin::theme"] {
+            assert_eq!(&*registry.fetch(id).unwrap().command, "!theme", "{id}");
+        }
+
+        let cmd = registry.fetch("builtin::change").unwrap();
+        assert_eq!(&*cmd.subcommands[0].command, "set");
+
+        assert!(registry.fetch("builtin::!nope").is_err());
+        assert!(registry.fetch("other::!theme").is_err());
+        assert!(registry.fetch("!theme").is_err());
+    }
+}
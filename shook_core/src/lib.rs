@@ -1,11 +1,21 @@
 use std::{future::Future, pin::Pin};
 
+// `#[command]` refers to `::shook_core`, which this makes work in here too
+extern crate self as shook_core;
+
 pub mod help;
 
 pub mod args;
 pub mod callable;
+pub mod locale;
 pub mod message;
+pub mod outbox;
+pub mod prefix;
 pub mod render;
+pub mod schedule;
+
+mod clock;
+pub use clock::Clock;
 
 mod format;
 pub use format::{FormatTime, IterExt};
@@ -15,7 +25,7 @@ pub use config::{ConfigPath, PersistFromConfig};
 
 mod state;
 
-// #[cfg(test)]
+#[cfg(any(test, feature = "testing"))]
 pub mod testing;
 
 type BoxedFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a + Send>>;
@@ -27,11 +37,15 @@ pub mod prelude {
     #[derive(Clone, Debug)]
     pub struct StreamerName(pub String);
 
-    pub use crate::callable::{self, Binding, Command, IntoCallable, SharedCallable};
+    pub use crate::callable::{self, Binding, Command, IntoCallable, Permission, SharedCallable};
+    pub use shook_macros::command;
     pub type SharedRegistry = Arc<crate::help::Registry>;
+    pub type SharedCatalog = Arc<crate::locale::Catalog>;
+    pub use crate::locale::Locale;
     pub use crate::message::Message;
-    pub use crate::render::{Render, RenderFlavor, Response, Simple};
-    pub use crate::state::{GlobalState, State};
+    pub use crate::prefix::Prefix;
+    pub use crate::render::{Embed, Render, RenderFlavor, Response, Simple, Text};
+    pub use crate::state::{GlobalState, State, Watch};
 }
 
 pub const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
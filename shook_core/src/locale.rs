@@ -0,0 +1,337 @@
+use std::{collections::HashMap, fmt::Display, sync::Arc};
+
+use crate::{prelude::SharedCatalog, render::Text, state::GlobalState};
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+// these are used when no catalog has a key, so a response is never missing
+const ENGLISH: &[(&str, &str)] = &[
+    ("unknown", "I don't know"),
+    (
+        "unsupported-transport",
+        "that isn't usable on this transport",
+    ),
+    ("cooldown", "that is on cooldown, {left} left"),
+    ("permission.everyone", "that is usable by everyone"),
+    (
+        "permission.subscriber",
+        "that requires you to be a subscriber",
+    ),
+    ("permission.vip", "that requires you to be a vip"),
+    (
+        "permission.moderator",
+        "that requires you to be a moderator",
+    ),
+    (
+        "permission.broadcaster",
+        "that requires you to be the broadcaster",
+    ),
+    (
+        "permission.bot-owner",
+        "that requires you to be the bot owner",
+    ),
+    (
+        "permission.elevated",
+        "that requires you to be a moderator or the broadcaster",
+    ),
+    ("args.required", "an argument is required: {usage}"),
+    ("args.no-match", "invalid arguments: {usage}"),
+    ("args.invalid", "expected {expected} for <{key}>"),
+    ("args.invalid-flag", "expected {expected} for {key}"),
+    ("args.malformed", "invalid arguments: {reason}"),
+    ("args.expected.string", "some text"),
+    ("args.expected.number", "a number"),
+    ("args.expected.user", "a user name"),
+    ("args.expected.duration", "a duration (like 1h30m)"),
+    ("args.expected.choice", "one of: {choices}"),
+    ("args.unknown-flag", "unknown flag: --{name}"),
+    ("args.flag-value", "--{key} does not take a value"),
+    ("args.unclosed-quote", "a quote was not closed"),
+    ("dispatch.listener", "a listener"),
+    ("dispatch.timeout", "{name} took too long"),
+    ("dispatch.panic", "{name} crashed"),
+    ("dispatch.cancelled", "{name} was cancelled"),
+    ("help.not-found", "cannot find '{command}'"),
+    ("split.more", "…and {count} more"),
+    ("crates.not-found", "I cannot find anything for: {name}"),
+    (
+        "crates.no-exact-match",
+        "I cannot find an exact match for: {name}",
+    ),
+    ("user-defined.created", "created {command} -> {body}"),
+    ("user-defined.updated", "updated {command} -> {body}"),
+    ("user-defined.removed", "removed: {command}"),
+    ("user-defined.aliased", "aliased {from} to {to}"),
+    ("user-defined.exists", "{command} already exists"),
+    ("user-defined.not-found", "{command} wasn't found"),
+    ("user-defined.builtin", "{command} is a built-in command"),
+    (
+        "user-defined.empty-name",
+        "the command name cannot be empty",
+    ),
+    (
+        "user-defined.empty-body",
+        "the command body cannot be empty",
+    ),
+];
+
+// this is keyed by locale, then by message key. templates use `{name}` for their arguments
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Catalog {
+    #[serde(flatten)]
+    map: HashMap<String, HashMap<String, String>>,
+}
+
+impl Catalog {
+    // this tries `pt-br`, then `pt`, then the default locale, then the built-in english
+    pub fn get(&self, locale: &str, key: &str) -> Option<&str> {
+        let language = locale.split(['-', '_']).next().unwrap_or(locale);
+        [locale, language, DEFAULT_LOCALE]
+            .into_iter()
+            .find_map(|locale| self.map.get(locale)?.get(key))
+            .map(|s| &**s)
+            .or_else(|| {
+                ENGLISH
+                    .iter()
+                    .find_map(|&(k, template)| (k == key).then_some(template))
+            })
+    }
+
+    pub fn locales(&self) -> impl Iterator<Item = &str> {
+        self.map.keys().map(|s| &**s)
+    }
+
+    pub fn add(&mut self, locale: &str, key: &str, template: impl ToString) {
+        self.map
+            .entry(locale.to_string())
+            .or_default()
+            .insert(key.to_string(), template.to_string());
+    }
+
+    // this catches templates that would never be filled in, when the catalog is loaded
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut errors = self
+            .map
+            .iter()
+            .flat_map(|(locale, map)| {
+                map.iter().filter_map(move |(key, template)| {
+                    let err = check_template(template).err()?;
+                    Some(format!("{locale}::{key}: {err}"))
+                })
+            })
+            .collect::<Vec<_>>();
+        errors.sort();
+
+        anyhow::ensure!(
+            errors.is_empty(),
+            "invalid catalog entries:\n{}",
+            errors.join("\n")
+        );
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LocaleConfig {
+    pub default: String,
+    // this is keyed by the message source, e.g. `#museun` on twitch or `general` on discord,
+    // which uses the channel name rather than its id
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub channels: HashMap<String, String>,
+}
+
+impl Default for LocaleConfig {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_LOCALE.to_string(),
+            channels: HashMap::new(),
+        }
+    }
+}
+
+impl LocaleConfig {
+    pub fn for_channel(&self, channel: &str) -> &str {
+        self.channels.get(channel).unwrap_or(&self.default)
+    }
+}
+
+// a catalog with a locale picked, this is what handlers use
+#[derive(Clone, Debug)]
+pub struct Locale {
+    catalog: Arc<Catalog>,
+    name: Arc<str>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self::new(Arc::default(), DEFAULT_LOCALE)
+    }
+}
+
+impl Locale {
+    pub fn new(catalog: Arc<Catalog>, name: &str) -> Self {
+        Self {
+            catalog,
+            name: name.into(),
+        }
+    }
+
+    // this is the catalog with `channel`'s locale picked
+    pub fn for_channel(state: &GlobalState, channel: &str) -> Self {
+        let catalog = state.try_get_owned::<SharedCatalog>().unwrap_or_default();
+        match state.try_get::<LocaleConfig>() {
+            Some(config) => Self::new(catalog, config.for_channel(channel)),
+            None => Self::new(catalog, DEFAULT_LOCALE),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.catalog.get(&self.name, key)
+    }
+
+    pub fn tr(&self, key: &str) -> String {
+        self.tr_with(key, &[])
+    }
+
+    // an unknown key is shown as-is, so it is obvious what is missing
+    pub fn tr_with(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut out = String::new();
+        fill(self.template(key), args, |piece| match piece {
+            Piece::Text(text) => out.push_str(text),
+            Piece::Arg(arg) => out.push_str(&arg.to_string()),
+        });
+        out
+    }
+
+    // this is like `tr_with`, but the arguments are shown as code
+    pub fn tr_code(&self, key: &str, args: &[(&str, &str)]) -> Text {
+        let mut out = Text::new();
+        fill(self.template(key), args, |piece| {
+            out = match piece {
+                Piece::Text("") => std::mem::take(&mut out),
+                Piece::Text(text) => std::mem::take(&mut out).text(text),
+                Piece::Arg(arg) => std::mem::take(&mut out).code(arg),
+            }
+        });
+        out
+    }
+
+    fn template<'a>(&'a self, key: &'a str) -> &'a str {
+        self.get(key).unwrap_or_else(|| {
+            log::warn!("no message for '{key}' in '{}'", self.name);
+            key
+        })
+    }
+}
+
+enum Piece<'a, T: ?Sized> {
+    Text(&'a str),
+    Arg(&'a T),
+}
+
+// unknown arguments are left alone
+fn fill<'a, T: ?Sized>(template: &'a str, args: &[(&str, &'a T)], mut f: impl FnMut(Piece<'a, T>)) {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        f(Piece::Text(&rest[..start]));
+        rest = &rest[start..];
+
+        let arg = rest.find('}').and_then(|end| {
+            let (_, value) = args.iter().find(|(name, _)| *name == &rest[1..end])?;
+            Some((end, *value))
+        });
+
+        match arg {
+            Some((end, arg)) => {
+                f(Piece::Arg(arg));
+                rest = &rest[end + 1..];
+            }
+            None => {
+                f(Piece::Text("{"));
+                rest = &rest[1..];
+            }
+        }
+    }
+    f(Piece::Text(rest))
+}
+
+fn check_template(template: &str) -> anyhow::Result<()> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        let end = rest
+            .find('}')
+            .ok_or_else(|| anyhow::anyhow!("'{{' is never closed"))?;
+        let name = &rest[..end];
+        anyhow::ensure!(
+            !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_'),
+            "'{{{name}}}' is not a valid argument"
+        );
+        rest = &rest[end + 1..];
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prelude::{GlobalState, Message, Render as _, RenderFlavor, Response, State},
+        testing::{BuildTestMessage as _, MockMessage},
+    };
+
+    #[test]
+    fn channels_are_keyed_by_the_source() {
+        let mut catalog = Catalog::default();
+        catalog.add("de", "unknown", "das weiß ich nicht");
+
+        let mut state = State::default();
+        state.insert(Arc::new(catalog));
+        state.insert(LocaleConfig {
+            channels: HashMap::from([
+                (String::from("#museun"), String::from("de")),
+                // discord messages use the channel name
+                (String::from("general"), String::from("de")),
+            ]),
+            ..LocaleConfig::default()
+        });
+        let state = GlobalState::new(state);
+
+        for (channel, expected) in [
+            ("#museun", "das weiß ich nicht"),
+            ("general", "das weiß ich nicht"),
+            ("123456789", "I don't know"),
+        ] {
+            let msg = Message::new(MockMessage::default().with_channel(channel), state.clone());
+            assert_eq!(msg.tr("unknown"), expected, "{channel}");
+        }
+    }
+
+    #[test]
+    fn fill() {
+        let locale = Locale::default();
+        assert_eq!(
+            locale.tr_with("cooldown", &[("left", &"5s"), ("unused", &1)]),
+            "that is on cooldown, 5s left"
+        );
+        assert_eq!(
+            locale.tr_with("cooldown", &[]),
+            "that is on cooldown, {left} left"
+        );
+        assert_eq!(locale.tr("no.such.key"), "no.such.key");
+
+        let text = locale.tr_code(
+            "user-defined.created",
+            &[("command", "!hi"), ("body", "hello")],
+        );
+        assert_eq!(
+            text.render(RenderFlavor::Discord),
+            [Response::Say(String::from("created `!hi` -> `hello`"))]
+        );
+    }
+}
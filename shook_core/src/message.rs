@@ -1,6 +1,13 @@
-use std::sync::Arc;
+use std::{ops::Range, sync::Arc};
 
-use crate::{args::Arguments, state::GlobalState};
+use crate::{
+    args::Arguments,
+    callable::{Command, Guard, Permission},
+    locale::Locale,
+    prefix::{self, Prefixes},
+    render::Text,
+    state::GlobalState,
+};
 
 pub trait MessageType
 where
@@ -15,6 +22,12 @@ where
     fn is_from_moderator(&self) -> bool {
         false
     }
+    fn is_from_vip(&self) -> bool {
+        false
+    }
+    fn is_from_subscriber(&self) -> bool {
+        false
+    }
     fn is_from_twitch(&self) -> bool {
         false
     }
@@ -25,6 +38,10 @@ pub struct Message {
     inner: Arc<dyn MessageType>,
     state: GlobalState,
     args: Option<Arguments>,
+    prefixes: Arc<Prefixes>,
+    command: Option<Range<usize>>,
+    selected: Option<Arc<Command>>,
+    guard: Option<Guard>,
 }
 
 impl Message {
@@ -33,7 +50,18 @@ impl Message {
             inner: Arc::new(inner),
             state,
             args: None,
+            prefixes: Arc::default(),
+            command: None,
+            selected: None,
+            guard: None,
         }
+        .with_prefixes(Prefixes::default())
+    }
+
+    pub fn with_prefixes(mut self, prefixes: Prefixes) -> Self {
+        self.command = prefixes.find_command(self.inner.data());
+        self.prefixes = Arc::new(prefixes);
+        self
     }
 
     pub fn data(&self) -> &str {
@@ -49,11 +77,32 @@ impl Message {
     }
 
     pub fn match_command(&self, right: &str) -> bool {
-        self.command() == Self::split_command(right)
+        self.is_command() && self.command() == prefix::strip_marker(Self::split_command(right))
     }
 
+    pub fn is_command(&self) -> bool {
+        self.command.is_some()
+    }
+
+    // this is the command name without its prefix, or empty if this isn't a command
     pub fn command(&self) -> &str {
-        Self::split_command(self.data())
+        self.command.clone().map_or("", |range| &self.data()[range])
+    }
+
+    // this is everything after the command name
+    pub fn input(&self) -> &str {
+        self.command
+            .as_ref()
+            .map_or("", |range| self.data()[range.end..].trim_start())
+    }
+
+    pub fn prefixes(&self) -> &Prefixes {
+        &self.prefixes
+    }
+
+    // this shows a declared command name (e.g. `!help`) with this channel's prefix
+    pub fn display_command(&self, name: &str) -> String {
+        self.prefixes.display(name)
     }
 
     pub fn args(&self) -> &Arguments {
@@ -68,38 +117,66 @@ impl Message {
         &self.state
     }
 
-    pub async fn streamer_name(&self) -> String {
+    pub fn streamer_name(&self) -> anyhow::Result<String> {
         self.state
-            .get_owned::<crate::prelude::StreamerName>()
-            .await
-            .0
+            .get::<crate::prelude::StreamerName>()
+            .map(|name| name.0.clone())
+    }
+
+    pub fn is_from_owner(&self) -> bool {
+        matches!(self.streamer_name(), Ok(name) if name == self.sender_name())
+    }
+
+    pub fn permission(&self) -> Permission {
+        if self.is_from_owner() {
+            return Permission::BotOwner;
+        }
+
+        [
+            (self.is_from_broadcaster(), Permission::Broadcaster),
+            (self.is_from_moderator(), Permission::Moderator),
+            (self.is_from_vip(), Permission::Vip),
+            (self.is_from_subscriber(), Permission::Subscriber),
+        ]
+        .into_iter()
+        .find_map(|(ok, permission)| ok.then_some(permission))
+        .unwrap_or_default()
+    }
+
+    // this is the catalog with this channel's locale picked
+    pub fn locale(&self) -> Locale {
+        Locale::for_channel(&self.state, self.source())
     }
 
-    pub async fn is_from_owner(&self) -> bool {
-        self.sender_name() == self.streamer_name().await
+    pub fn tr(&self, key: &str) -> String {
+        self.locale().tr(key)
+    }
+
+    pub fn tr_with(&self, key: &str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+        self.locale().tr_with(key, args)
+    }
+
+    pub fn tr_code(&self, key: &str, args: &[(&str, &str)]) -> Text {
+        self.locale().tr_code(key, args)
     }
 
     pub fn require_moderator(&self) -> anyhow::Result<()> {
-        anyhow::ensure!(
-            self.is_from_moderator(),
-            "that requires you to be a moderator"
-        );
-        Ok(())
+        self.require(self.is_from_moderator(), "permission.moderator")
     }
 
     pub fn require_broadcaster(&self) -> anyhow::Result<()> {
-        anyhow::ensure!(
-            self.is_from_broadcaster(),
-            "that requires you to be the broadcaster"
-        );
-        Ok(())
+        self.require(self.is_from_broadcaster(), "permission.broadcaster")
     }
 
     pub fn require_elevation(&self) -> anyhow::Result<()> {
-        anyhow::ensure!(
+        self.require(
             self.is_from_moderator() || self.is_from_broadcaster(),
-            "that requires you to be a moderator or the broadcaster"
-        );
+            "permission.elevated",
+        )
+    }
+
+    fn require(&self, ok: bool, key: &str) -> anyhow::Result<()> {
+        anyhow::ensure!(ok, self.tr(key));
         Ok(())
     }
 
@@ -111,6 +188,34 @@ impl Message {
         self.inner.is_from_moderator()
     }
 
+    pub fn is_from_vip(&self) -> bool {
+        self.inner.is_from_vip()
+    }
+
+    pub fn is_from_subscriber(&self) -> bool {
+        self.inner.is_from_subscriber()
+    }
+
+    pub(crate) fn select_command(&mut self, cmd: Arc<Command>) {
+        self.selected.replace(cmd);
+    }
+
+    // when a command was picked for this message, every other command ignores it
+    pub(crate) fn is_selected(&self, cmd: &Arc<Command>) -> bool {
+        match &self.selected {
+            Some(selected) => Arc::ptr_eq(selected, cmd),
+            None => true,
+        }
+    }
+
+    pub(crate) fn set_guard(&mut self, guard: Guard) {
+        self.guard.replace(guard);
+    }
+
+    pub(crate) fn guard(&self) -> Option<Guard> {
+        self.guard
+    }
+
     pub(super) fn get_args(&mut self) -> &mut Option<Arguments> {
         &mut self.args
     }
@@ -0,0 +1,86 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::Context;
+
+use crate::{
+    prelude::Locale,
+    render::{split, Limits, Render, RenderFlavor, Response},
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Transport {
+    Twitch,
+    Discord,
+    Local,
+}
+
+impl Transport {
+    pub const fn flavor(&self) -> RenderFlavor {
+        match self {
+            Self::Twitch => RenderFlavor::Twitch,
+            Self::Discord => RenderFlavor::Discord,
+            Self::Local => RenderFlavor::Plain,
+        }
+    }
+}
+
+impl std::fmt::Display for Transport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Twitch => "twitch",
+            Self::Discord => "discord",
+            Self::Local => "local",
+        })
+    }
+}
+
+// each transport registers one of these, `channel` is whatever that transport uses to name a channel
+#[async_trait::async_trait]
+pub trait Sender
+where
+    Self: Send + Sync + 'static,
+{
+    async fn send(&self, channel: &str, responses: Vec<Response>) -> anyhow::Result<()>;
+}
+
+// this lets anything with the state post to a channel, without a message to respond to
+#[derive(Clone, Default)]
+pub struct Outbox {
+    senders: Arc<RwLock<HashMap<Transport, Arc<dyn Sender>>>>,
+}
+
+impl Outbox {
+    pub fn register(&self, transport: Transport, sender: impl Sender) {
+        let mut senders = self.senders.write().unwrap();
+        if senders.insert(transport, Arc::new(sender)).is_some() {
+            log::warn!("replaced the sender for {transport}");
+        }
+    }
+
+    pub fn sender(&self, transport: Transport) -> Option<Arc<dyn Sender>> {
+        self.senders.read().unwrap().get(&transport).cloned()
+    }
+
+    pub async fn send(
+        &self,
+        transport: Transport,
+        channel: &str,
+        render: impl Render,
+        locale: &Locale,
+    ) -> anyhow::Result<()> {
+        let sender = self
+            .sender(transport)
+            .with_context(|| format!("{transport} is not connected"))?;
+
+        let flavor = transport.flavor();
+        let responses = split(render.render(flavor), Limits::for_flavor(flavor), locale);
+        if responses.is_empty() {
+            return Ok(());
+        }
+        sender.send(channel, responses).await
+    }
+}
@@ -0,0 +1,174 @@
+use std::{collections::HashMap, ops::Range};
+
+// commands are declared with a leading `!`, which stands in for whichever
+// prefix the channel the message came from uses
+pub const MARKER: char = '!';
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Prefix {
+    pub prefixes: Vec<String>,
+    // this allows mentioning the bot instead of using a prefix
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub mention: bool,
+    // these replace `prefixes` for the named channels
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub channels: HashMap<String, Vec<String>>,
+}
+
+impl Default for Prefix {
+    fn default() -> Self {
+        Self {
+            prefixes: vec![String::from(MARKER)],
+            mention: false,
+            channels: HashMap::new(),
+        }
+    }
+}
+
+impl Prefix {
+    pub fn for_channel(&self, channel: &str) -> &[String] {
+        self.channels.get(channel).unwrap_or(&self.prefixes)
+    }
+
+    // `mentions` are the ways the transport spells a mention of the bot
+    pub fn resolve(&self, channel: &str, mentions: &[String]) -> Prefixes {
+        Prefixes {
+            symbols: self.for_channel(channel).to_vec(),
+            mentions: match self.mention {
+                true => mentions.to_vec(),
+                false => Vec::new(),
+            },
+        }
+    }
+}
+
+// the prefixes which apply to a single channel
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Prefixes {
+    symbols: Vec<String>,
+    mentions: Vec<String>,
+}
+
+impl Default for Prefixes {
+    fn default() -> Self {
+        Prefix::default().resolve("", &[])
+    }
+}
+
+impl Prefixes {
+    // this is the span of the command name, without its prefix
+    pub fn find_command(&self, input: &str) -> Option<Range<usize>> {
+        let symbols = self.symbols.iter().filter_map(|p| {
+            // a prefix must be directly attached to the command name
+            input.strip_prefix(&**p)
+        });
+        let mentions = self.mentions.iter().filter_map(|p| {
+            input
+                .strip_prefix(&**p)
+                .filter(|s| s.starts_with(char::is_whitespace))
+                .map(str::trim_start)
+        });
+
+        // the longest prefix wins, so `!!` can live next to `!`
+        let rest = symbols
+            .chain(mentions)
+            .filter(|rest| !rest.is_empty() && !rest.starts_with(char::is_whitespace))
+            .min_by_key(|rest| rest.len())?;
+
+        let start = input.len() - rest.len();
+        let end = rest
+            .find(char::is_whitespace)
+            .map_or(input.len(), |pos| start + pos);
+        Some(start..end)
+    }
+
+    // this is how commands should be shown to users of this channel
+    pub fn display(&self, name: &str) -> String {
+        let name = name.strip_prefix(MARKER).unwrap_or(name);
+        match (self.symbols.first(), self.mentions.first()) {
+            (Some(prefix), _) => format!("{prefix}{name}"),
+            (None, Some(mention)) => format!("{mention} {name}"),
+            (None, None) => name.to_string(),
+        }
+    }
+
+    // this turns a command name written with any of the prefixes into its declared form
+    pub fn canonical(&self, name: &str) -> String {
+        let name = self
+            .symbols
+            .iter()
+            .filter_map(|p| name.strip_prefix(&**p))
+            .min_by_key(|s| s.len())
+            .unwrap_or(name);
+        let name = name.strip_prefix(MARKER).unwrap_or(name);
+        format!("{MARKER}{name}")
+    }
+}
+
+pub fn strip_marker(name: &str) -> &str {
+    name.strip_prefix(MARKER).unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command<'a>(prefixes: &Prefixes, input: &'a str) -> Option<&'a str> {
+        prefixes.find_command(input).map(|range| &input[range])
+    }
+
+    fn discord() -> Vec<String> {
+        vec![String::from("<@42>"), String::from("<@!42>")]
+    }
+
+    #[test]
+    fn resolve() {
+        let prefix = Prefix {
+            prefixes: vec![String::from("!"), String::from("!!")],
+            mention: false,
+            channels: HashMap::from([(String::from("#other"), vec![String::from("?")])]),
+        };
+
+        let prefixes = prefix.resolve("#shook", &discord());
+        assert_eq!(command(&prefixes, "!hello world"), Some("hello"));
+        assert_eq!(command(&prefixes, "!!hello"), Some("hello"));
+        assert_eq!(command(&prefixes, "?hello"), None);
+        assert_eq!(command(&prefixes, "! hello"), None);
+        assert_eq!(command(&prefixes, "!"), None);
+        // mentions weren't enabled
+        assert_eq!(command(&prefixes, "<@42> hello"), None);
+
+        let prefixes = prefix.resolve("#other", &discord());
+        assert_eq!(command(&prefixes, "?hello"), Some("hello"));
+        assert_eq!(command(&prefixes, "!hello"), None);
+        assert_eq!(prefixes.display("!hello"), "?hello");
+        assert_eq!(prefixes.canonical("?hello"), "!hello");
+    }
+
+    #[test]
+    fn discord_mentions() {
+        let prefix = Prefix {
+            mention: true,
+            ..Prefix::default()
+        };
+
+        let prefixes = prefix.resolve("#shook", &discord());
+        assert_eq!(command(&prefixes, "<@42> hello there"), Some("hello"));
+        assert_eq!(command(&prefixes, "<@!42>   hello"), Some("hello"));
+        assert_eq!(command(&prefixes, "!hello"), Some("hello"));
+
+        // the mention must be followed by a space, and must be for the bot
+        assert_eq!(command(&prefixes, "<@42>hello"), None);
+        assert_eq!(command(&prefixes, "<@43> hello"), None);
+        assert_eq!(command(&prefixes, "<@42>"), None);
+
+        let prefix = Prefix {
+            prefixes: vec![],
+            mention: true,
+            ..Prefix::default()
+        };
+        let prefixes = prefix.resolve("#shook", &discord());
+        assert_eq!(prefixes.display("!hello"), "<@42> hello");
+    }
+}
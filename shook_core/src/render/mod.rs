@@ -0,0 +1,341 @@
+use crate::{
+    callable::{Dispatch, DispatchConfig, SharedCallable},
+    prelude::Message,
+    schedule::Activity,
+};
+
+mod split;
+pub use split::{split, Limits};
+
+mod text;
+pub use text::Text;
+
+// transcripts write these as `kind: say` and `text: hello`
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "text")]
+pub enum Response {
+    Say(String),
+    Reply(String),
+    Problem(String),
+    // a `/me` on twitch
+    Action(String),
+    // a whisper on twitch, or a direct message on discord, to the sender
+    Private(String),
+    Embed(Embed),
+    // this reacts to the message that was responded to
+    React(String),
+    // this deletes the message that was responded to
+    Delete,
+}
+
+impl Response {
+    pub fn builder() -> ResponseBuilder {
+        ResponseBuilder::default()
+    }
+    pub fn say(data: impl Into<String>) -> ResponseBuilder {
+        Self::builder().say(data)
+    }
+    pub fn reply(data: impl Into<String>) -> ResponseBuilder {
+        Self::builder().reply(data)
+    }
+    pub fn problem(data: impl Into<String>) -> ResponseBuilder {
+        Self::builder().problem(data)
+    }
+    pub fn action(data: impl Into<String>) -> ResponseBuilder {
+        Self::builder().action(data)
+    }
+    pub fn private(data: impl Into<String>) -> ResponseBuilder {
+        Self::builder().private(data)
+    }
+    pub fn embed(embed: Embed) -> ResponseBuilder {
+        Self::builder().embed(embed)
+    }
+    pub fn react(emoji: impl Into<String>) -> ResponseBuilder {
+        Self::builder().react(emoji)
+    }
+    pub fn delete() -> ResponseBuilder {
+        Self::builder().delete()
+    }
+}
+
+#[derive(Default)]
+pub struct ResponseBuilder(Vec<Response>);
+impl ResponseBuilder {
+    pub fn say(mut self, data: impl Into<String>) -> Self {
+        self.0.push(Response::Say(data.into()));
+        self
+    }
+    pub fn reply(mut self, data: impl Into<String>) -> Self {
+        self.0.push(Response::Reply(data.into()));
+        self
+    }
+    pub fn problem(mut self, data: impl Into<String>) -> Self {
+        self.0.push(Response::Problem(data.into()));
+        self
+    }
+    pub fn action(mut self, data: impl Into<String>) -> Self {
+        self.0.push(Response::Action(data.into()));
+        self
+    }
+    pub fn private(mut self, data: impl Into<String>) -> Self {
+        self.0.push(Response::Private(data.into()));
+        self
+    }
+    pub fn embed(mut self, embed: Embed) -> Self {
+        self.0.push(Response::Embed(embed));
+        self
+    }
+    pub fn react(mut self, emoji: impl Into<String>) -> Self {
+        self.0.push(Response::React(emoji.into()));
+        self
+    }
+    pub fn delete(mut self) -> Self {
+        self.0.push(Response::Delete);
+        self
+    }
+    pub fn finish(self) -> Vec<Response> {
+        self.0
+    }
+}
+
+impl Render for ResponseBuilder {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        self.0.render(flavor)
+    }
+}
+
+pub async fn dispatch_and_render(
+    callables: &[SharedCallable],
+    msg: &Message,
+    flavor: RenderFlavor,
+) -> Vec<Response> {
+    msg.state().get_or_default::<Activity>().record();
+
+    let config = msg
+        .state()
+        .try_get_owned::<DispatchConfig>()
+        .unwrap_or_default();
+
+    let responses = Dispatch::new(callables)
+        .with_config(&config)
+        .into_render(msg)
+        .await
+        .render(flavor);
+
+    let limits = Limits::for_flavor(flavor).with_max_messages(config.max_messages);
+    split(responses, limits, &msg.locale())
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Embed {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<EmbedField>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EmbedField {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub inline: bool,
+}
+
+impl Embed {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url.replace(url.into());
+        self
+    }
+
+    pub const fn color(mut self, color: u32) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn field(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.add_field(name.into(), value.into(), false)
+    }
+
+    pub fn inline_field(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.add_field(name.into(), value.into(), true)
+    }
+
+    fn add_field(mut self, name: String, value: String, inline: bool) -> Self {
+        self.fields.push(EmbedField {
+            name,
+            value,
+            inline,
+        });
+        self
+    }
+
+    // this is how an embed looks where embeds aren't supported
+    pub fn to_text(&self) -> String {
+        use std::fmt::Write as _;
+
+        let mut out = self.title.clone();
+        if let Some(url) = &self.url {
+            let _ = write!(&mut out, " ({url})");
+        }
+        for EmbedField { name, value, .. } in &self.fields {
+            let _ = write!(&mut out, " | {name}: {value}");
+        }
+        out
+    }
+}
+
+// embeds are only sent to discord, everywhere else gets the text
+impl Render for Embed {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        match flavor {
+            RenderFlavor::Twitch | RenderFlavor::Plain => vec![Response::Say(self.to_text())],
+            RenderFlavor::Discord => vec![Response::Embed(self.clone())],
+        }
+    }
+}
+
+#[non_exhaustive]
+#[derive(
+    Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderFlavor {
+    Twitch,
+    Discord,
+    // this has no markup and no limits, like a terminal
+    Plain,
+}
+
+pub trait Render
+where
+    Self: Send + Sync,
+{
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response>;
+    fn boxed(self) -> Box<dyn Render>
+    where
+        Self: Sized + 'static,
+    {
+        Box::new(self)
+    }
+}
+
+impl<L: Render, R: Render> Render for (L, R) {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        match flavor {
+            RenderFlavor::Twitch | RenderFlavor::Plain => self.0.render(flavor),
+            RenderFlavor::Discord => self.1.render(flavor),
+        }
+    }
+}
+
+pub struct Simple<L, R> {
+    pub twitch: L,
+    pub discord: R,
+}
+
+impl<L: Render, R: Render> Render for Simple<L, R> {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        match flavor {
+            RenderFlavor::Twitch | RenderFlavor::Plain => self.twitch.render(flavor),
+            RenderFlavor::Discord => self.discord.render(flavor),
+        }
+    }
+}
+
+pub type BoxedRender = Box<dyn Render>;
+
+impl Render for BoxedRender {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        (**self).render(flavor)
+    }
+
+    #[inline(always)]
+    fn boxed(self) -> Self {
+        self
+    }
+}
+
+impl<T: Render> Render for &T {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        (*self).render(flavor)
+    }
+}
+
+impl Render for Response {
+    fn render(&self, _: RenderFlavor) -> Vec<Response> {
+        vec![self.clone()]
+    }
+}
+
+impl<T: Render, const N: usize> Render for [T; N] {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        self.iter().flat_map(|this| this.render(flavor)).collect()
+    }
+}
+
+impl<T: Render> Render for Vec<T> {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        self.iter().flat_map(|this| this.render(flavor)).collect()
+    }
+}
+
+impl Render for str {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        if self.trim().is_empty() {
+            return vec![];
+        }
+        self.to_string().render(flavor)
+    }
+}
+
+impl Render for &'static str {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        if self.trim().is_empty() {
+            return vec![];
+        }
+        (*self).render(flavor)
+    }
+}
+
+impl Render for String {
+    fn render(&self, _: RenderFlavor) -> Vec<Response> {
+        if self.trim().is_empty() {
+            return vec![];
+        }
+        vec![Response::Say(self.to_string())]
+    }
+}
+
+impl Render for () {
+    fn render(&self, _: RenderFlavor) -> Vec<Response> {
+        vec![]
+    }
+}
+
+impl<T: Render> Render for anyhow::Result<T> {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        match self {
+            Ok(r) => r.render(flavor),
+            Err(e) => vec![Response::Problem(e.to_string())],
+        }
+    }
+}
+
+impl<T: Render> Render for Option<T> {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        self.as_ref()
+            .map(|this| this.render(flavor))
+            .unwrap_or_default()
+    }
+}
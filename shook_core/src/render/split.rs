@@ -0,0 +1,377 @@
+use super::{RenderFlavor, Response};
+use crate::prelude::Locale;
+
+// transports prepend things like the sender's name, so this is left free at the end of each message
+const HEADROOM: usize = 32;
+
+const FENCE: &str = "```";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Limits {
+    // this is in characters
+    pub max_len: usize,
+    pub max_messages: usize,
+    // twitch cannot send a newline, so each line is its own message
+    pub split_lines: bool,
+    pub code_fences: bool,
+}
+
+impl Limits {
+    pub const fn for_flavor(flavor: RenderFlavor) -> Self {
+        match flavor {
+            RenderFlavor::Twitch => Self {
+                max_len: 500,
+                max_messages: 5,
+                split_lines: true,
+                code_fences: false,
+            },
+            RenderFlavor::Discord => Self {
+                max_len: 2000,
+                max_messages: 5,
+                split_lines: false,
+                code_fences: true,
+            },
+            RenderFlavor::Plain => Self {
+                max_len: usize::MAX,
+                max_messages: usize::MAX,
+                split_lines: false,
+                code_fences: false,
+            },
+        }
+    }
+
+    pub const fn with_max_messages(mut self, max_messages: usize) -> Self {
+        self.max_messages = max_messages;
+        self
+    }
+}
+
+// this splits long responses, and replaces anything past `max_messages` with "…and N more" in `locale`
+pub fn split(responses: Vec<Response>, limits: Limits, locale: &Locale) -> Vec<Response> {
+    let mut out = Vec::with_capacity(responses.len());
+    for resp in responses {
+        let (text, make): (_, fn(String) -> Response) = match resp {
+            Response::Say(text) => (text, Response::Say),
+            Response::Reply(text) => (text, Response::Reply),
+            Response::Problem(text) => (text, Response::Problem),
+            Response::Action(text) => (text, Response::Action),
+            Response::Private(text) => (text, Response::Private),
+            // a transport which cannot send a newline cannot show an embed either
+            Response::Embed(embed) if limits.split_lines => (embed.to_text(), Response::Say),
+            resp => {
+                out.push(resp);
+                continue;
+            }
+        };
+        out.extend(split_text(&text, limits).into_iter().map(make));
+    }
+
+    let total = out.iter().filter(|resp| is_text(resp)).count();
+    if total <= limits.max_messages {
+        return out;
+    }
+
+    // the last message is replaced with how many were left out
+    let keep = limits.max_messages.saturating_sub(1);
+    let mut seen = 0;
+    out.retain(|resp| {
+        if !is_text(resp) {
+            return true;
+        }
+        seen += 1;
+        seen <= keep
+    });
+    let more = locale.tr_with("split.more", &[("count", &(total - keep))]);
+    out.push(Response::Say(more));
+    out
+}
+
+const fn is_text(resp: &Response) -> bool {
+    !matches!(
+        resp,
+        Response::Embed(..) | Response::React(..) | Response::Delete
+    )
+}
+
+fn split_text(text: &str, limits: Limits) -> Vec<String> {
+    let max = limits.max_len.saturating_sub(HEADROOM).max(1);
+    if !limits.split_lines {
+        return split_block(text.trim(), max, limits.code_fences);
+    }
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .flat_map(|line| split_block(line, max, false))
+        .collect()
+}
+
+fn split_block(text: &str, max: usize, code_fences: bool) -> Vec<String> {
+    let mut out = vec![];
+    let mut rest = text;
+    // this is the opening line of the fence the last chunk ended inside of
+    let mut fence: Option<&str> = None;
+
+    while !rest.is_empty() {
+        let mut chunk = fence.map(|f| format!("{f}\n")).unwrap_or_default();
+        if chunk.chars().count() + rest.chars().count() <= max {
+            chunk.push_str(rest);
+            out.push(chunk);
+            break;
+        }
+
+        // room is left to close the fence, if the chunk ends inside of one
+        let closing = if code_fences { FENCE.len() + 1 } else { 0 };
+        let budget = max.saturating_sub(chunk.chars().count() + closing).max(1);
+        let (end, next) = find_cut(rest, budget);
+        let part = &rest[..end];
+
+        if code_fences {
+            fence = scan_fences(part, fence);
+        }
+        chunk.push_str(part.trim_end());
+        if fence.is_some() {
+            chunk.push('\n');
+            chunk.push_str(FENCE);
+        }
+
+        if !chunk.trim().is_empty() {
+            out.push(chunk);
+        }
+        // indentation matters inside of a code block
+        rest = match fence {
+            Some(..) => &rest[next..],
+            None => rest[next..].trim_start(),
+        };
+    }
+    out
+}
+
+// this prefers a newline, then whitespace, and finally just cuts the word
+fn find_cut(text: &str, budget: usize) -> (usize, usize) {
+    let limit = match text.char_indices().nth(budget) {
+        Some((i, _)) => i,
+        None => return (text.len(), text.len()),
+    };
+
+    let head = &text[..limit];
+    if let Some(i) = head.rfind('\n').filter(|&i| i > 0) {
+        return (i, i + 1);
+    }
+
+    match head
+        .char_indices()
+        .rev()
+        .find(|&(i, c)| i > 0 && c.is_whitespace())
+    {
+        Some((i, c)) => (i, i + c.len_utf8()),
+        None => (limit, limit),
+    }
+}
+
+fn scan_fences<'a>(text: &'a str, mut fence: Option<&'a str>) -> Option<&'a str> {
+    for line in text.lines().map(str::trim) {
+        if !line.starts_with(FENCE) {
+            continue;
+        }
+        // a fence that opens and closes on the same line doesn't change anything
+        if line.len() > FENCE.len() && line.ends_with(FENCE) {
+            continue;
+        }
+        fence = match fence {
+            Some(..) => None,
+            None => Some(line),
+        };
+    }
+    fence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::Embed;
+
+    #[test]
+    fn embeds_are_split_into_lines() {
+        let embed = Embed::new("hello\r\nPRIVMSG #other :world").field("a", "b\nc");
+
+        let responses = vec![Response::Embed(embed.clone())];
+        assert_eq!(
+            split(
+                responses.clone(),
+                Limits::for_flavor(RenderFlavor::Twitch),
+                &Locale::default()
+            ),
+            [
+                Response::Say(String::from("hello")),
+                Response::Say(String::from("PRIVMSG #other :world | a: b")),
+                Response::Say(String::from("c")),
+            ]
+        );
+
+        assert_eq!(
+            split(
+                responses,
+                Limits::for_flavor(RenderFlavor::Discord),
+                &Locale::default()
+            ),
+            [Response::Embed(embed)]
+        );
+    }
+
+    fn texts(responses: &[Response]) -> Vec<&str> {
+        responses
+            .iter()
+            .map(|resp| match resp {
+                Response::Say(text) => &**text,
+                resp => panic!("unexpected response: {resp:?}"),
+            })
+            .collect()
+    }
+
+    fn say(text: impl Into<String>) -> Vec<Response> {
+        vec![Response::Say(text.into())]
+    }
+
+    #[test]
+    fn headroom_is_left() {
+        let twitch = Limits::for_flavor(RenderFlavor::Twitch);
+        let discord = Limits::for_flavor(RenderFlavor::Discord);
+
+        for (limits, max) in [(twitch, 500 - HEADROOM), (discord, 2000 - HEADROOM)] {
+            let fits = "a".repeat(max);
+            assert_eq!(split(say(&*fits), limits, &Locale::default()), say(&*fits));
+
+            let over = "a".repeat(max + 1);
+            let out = split(say(over), limits, &Locale::default());
+            let lens = texts(&out).iter().map(|s| s.len()).collect::<Vec<_>>();
+            assert_eq!(lens.len(), 2);
+            assert!(lens.iter().all(|&len| len <= max), "{lens:?}");
+            assert_eq!(lens.iter().sum::<usize>(), max + 1);
+        }
+    }
+
+    #[test]
+    fn cuts_between_words() {
+        let text = ["hello"; 200].join(" ");
+        let out = split(
+            say(&*text),
+            Limits::for_flavor(RenderFlavor::Twitch),
+            &Locale::default(),
+        );
+
+        let parts = texts(&out);
+        assert_eq!(parts.len(), 3);
+        for part in &parts {
+            assert!(part.len() <= 500 - HEADROOM, "{part}");
+            assert!(part.split(' ').all(|word| word == "hello"), "{part}");
+        }
+        assert_eq!(parts.join(" "), text);
+    }
+
+    #[test]
+    fn cuts_at_lines() {
+        // twitch cannot send a newline, so every line is a message
+        let out = split(
+            say("one\n\n two \nthree"),
+            Limits::for_flavor(RenderFlavor::Twitch),
+            &Locale::default(),
+        );
+        assert_eq!(texts(&out), ["one", "two", "three"]);
+
+        // discord keeps the lines, but prefers to cut between them
+        let line = "word ".repeat(100);
+        let text = [line.trim(); 5].join("\n");
+        let out = split(
+            say(&*text),
+            Limits::for_flavor(RenderFlavor::Discord),
+            &Locale::default(),
+        );
+        let parts = texts(&out);
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0], [line.trim(); 3].join("\n"));
+        assert_eq!(parts[1], [line.trim(); 2].join("\n"));
+    }
+
+    #[test]
+    fn code_fences_are_reopened() {
+        let code = (0..200)
+            .map(|i| format!("    let value_{i} = {i};"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let text = format!("some code:\n```rust\n{code}\n```\ndone");
+        let out = split(
+            say(&*text),
+            Limits::for_flavor(RenderFlavor::Discord),
+            &Locale::default(),
+        );
+
+        let parts = texts(&out);
+        assert!(parts.len() > 1);
+        for (i, part) in parts.iter().enumerate() {
+            assert!(part.chars().count() <= 2000 - HEADROOM);
+            if i > 0 {
+                assert!(part.starts_with("```rust\n    let"), "{part}");
+            }
+            if i < parts.len() - 1 {
+                assert!(part.ends_with("\n```"), "{part}");
+            }
+        }
+        assert!(parts.last().unwrap().ends_with("```\ndone"));
+
+        // the indentation inside of the block is kept
+        assert_eq!(
+            parts
+                .iter()
+                .flat_map(|part| part.lines())
+                .filter(|line| line.starts_with("    let"))
+                .count(),
+            200
+        );
+    }
+
+    #[test]
+    fn extra_messages_are_counted() {
+        let text = (1..=8)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let out = split(
+            say(&*text),
+            Limits::for_flavor(RenderFlavor::Twitch),
+            &Locale::default(),
+        );
+        assert_eq!(texts(&out), ["1", "2", "3", "4", "…and 4 more"]);
+
+        let limits = Limits::for_flavor(RenderFlavor::Twitch).with_max_messages(8);
+        assert_eq!(split(say(&*text), limits, &Locale::default()).len(), 8);
+
+        // only text counts towards the limit
+        let mut responses = say("1\n2\n3\n4\n5\n6");
+        responses.push(Response::React(String::from("👍")));
+        let out = split(
+            responses,
+            Limits::for_flavor(RenderFlavor::Twitch).with_max_messages(2),
+            &Locale::default(),
+        );
+        assert_eq!(
+            out,
+            [
+                Response::Say(String::from("1")),
+                Response::React(String::from("👍")),
+                Response::Say(String::from("…and 5 more")),
+            ]
+        );
+    }
+
+    #[test]
+    fn extra_messages_are_translated() {
+        let mut catalog = crate::locale::Catalog::default();
+        catalog.add("de", "split.more", "…und {count} weitere");
+        let locale = Locale::new(std::sync::Arc::new(catalog), "de");
+
+        let limits = Limits::for_flavor(RenderFlavor::Twitch).with_max_messages(2);
+        let out = split(say("1\n2\n3"), limits, &locale);
+        assert_eq!(texts(&out), ["1", "…und 2 weitere"]);
+    }
+}
@@ -0,0 +1,157 @@
+use super::{Render, RenderFlavor, Response};
+
+// this is written once, and each flavor decides how it looks
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Text {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Code(String),
+    Bold(String),
+    Link(String),
+    // each item is on its own line
+    List(Vec<Text>),
+    Quote(Text),
+}
+
+impl Text {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(self, text: impl ToString) -> Self {
+        self.push(Part::Text(text.to_string()))
+    }
+
+    pub fn code(self, code: impl ToString) -> Self {
+        self.push(Part::Code(code.to_string()))
+    }
+
+    pub fn bold(self, text: impl ToString) -> Self {
+        self.push(Part::Bold(text.to_string()))
+    }
+
+    pub fn link(self, url: impl ToString) -> Self {
+        self.push(Part::Link(url.to_string()))
+    }
+
+    pub fn list<I>(self, items: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Self>,
+    {
+        self.push(Part::List(items.into_iter().map(Into::into).collect()))
+    }
+
+    pub fn quote(self, text: impl Into<Self>) -> Self {
+        self.push(Part::Quote(text.into()))
+    }
+
+    pub fn format(&self, flavor: RenderFlavor) -> String {
+        let mut out = String::new();
+        // lists and quotes end their line
+        let mut after_list = false;
+        for part in &self.parts {
+            if std::mem::take(&mut after_list) {
+                out.push('\n')
+            }
+
+            match (part, flavor) {
+                (Part::Text(text), _) => out.push_str(text),
+
+                (Part::Code(code), RenderFlavor::Discord) => {
+                    // the fence has to be longer than any run of backticks inside of the code
+                    let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+                    let fence = "`".repeat(longest + 1);
+                    // discord drops one space on each side, which keeps a backtick at the edge off the fence
+                    let pad = if code.starts_with('`') || code.ends_with('`') {
+                        " "
+                    } else {
+                        ""
+                    };
+                    out.push_str(&format!("{fence}{pad}{code}{pad}{fence}"))
+                }
+                (Part::Bold(text), RenderFlavor::Discord) => out.push_str(&format!("**{text}**")),
+                // this stops discord from embedding the link
+                (Part::Link(url), RenderFlavor::Discord) => out.push_str(&format!("<{url}>")),
+                (Part::Code(text) | Part::Bold(text) | Part::Link(text), _) => out.push_str(text),
+
+                (Part::List(items), _) => {
+                    if !out.is_empty() && !out.ends_with('\n') {
+                        out.push('\n')
+                    }
+                    let bullet = match flavor {
+                        RenderFlavor::Discord => "- ",
+                        _ => "",
+                    };
+                    let items = items
+                        .iter()
+                        .map(|item| format!("{bullet}{}", item.format(flavor)))
+                        .collect::<Vec<_>>();
+                    out.push_str(&items.join("\n"));
+                    after_list = true;
+                }
+
+                (Part::Quote(text), RenderFlavor::Discord) => {
+                    // a quote has to start on its own line
+                    if !out.is_empty() && !out.ends_with('\n') {
+                        out.push('\n')
+                    }
+                    let lines = text.format(flavor);
+                    let lines = lines.lines().map(|line| format!("> {line}"));
+                    out.push_str(&lines.collect::<Vec<_>>().join("\n"));
+                    after_list = true;
+                }
+                (Part::Quote(text), _) => out.push_str(&format!("\"{}\"", text.format(flavor))),
+            }
+        }
+        out
+    }
+
+    fn push(mut self, part: Part) -> Self {
+        self.parts.push(part);
+        self
+    }
+}
+
+impl From<&str> for Text {
+    fn from(text: &str) -> Self {
+        Self::new().text(text)
+    }
+}
+
+impl From<String> for Text {
+    fn from(text: String) -> Self {
+        Self::new().text(text)
+    }
+}
+
+impl Render for Text {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        self.format(flavor).render(flavor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn discord(code: &str) -> String {
+        Text::new().code(code).format(RenderFlavor::Discord)
+    }
+
+    #[test]
+    fn code_fences_are_longer_than_the_code() {
+        assert_eq!(discord("!hi"), "`!hi`");
+        assert_eq!(discord("a`b"), "``a`b``");
+        assert_eq!(discord("a``b`c"), "```a``b`c```");
+        assert_eq!(discord("`edge`"), "`` `edge` ``");
+        assert_eq!(
+            Text::new().code("a``b").format(RenderFlavor::Twitch),
+            "a``b"
+        );
+    }
+}
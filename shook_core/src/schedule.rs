@@ -0,0 +1,471 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::Context;
+use time::OffsetDateTime;
+use tokio::sync::broadcast;
+
+use crate::{
+    outbox::{Outbox, Transport},
+    prelude::{GlobalState, Locale},
+    render::{BoxedRender, Render, RenderFlavor, Response},
+    BoxedFuture,
+};
+
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    kind: Kind,
+    jitter: Duration,
+    live_only: bool,
+    min_messages: u64,
+}
+
+#[derive(Clone, Debug)]
+enum Kind {
+    Every(Duration),
+    Cron(Box<Cron>),
+}
+
+impl Schedule {
+    pub fn every(period: Duration) -> Self {
+        assert!(!period.is_zero(), "a schedule needs a non-zero period");
+        Self::new(Kind::Every(period))
+    }
+
+    // this is the usual 5 fields: minute hour day-of-month month day-of-week.
+    // it is evaluated in utc rather than local time, so `0 9 * * *` runs at 09:00 utc
+    pub fn cron(expr: &str) -> anyhow::Result<Self> {
+        Cron::parse(expr).map(|cron| Self::new(Kind::Cron(Box::new(cron))))
+    }
+
+    // each run is pushed back by a random amount, up to `jitter`
+    pub const fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    // this skips a run when the stream isn't live
+    pub const fn only_while_live(mut self) -> Self {
+        self.live_only = true;
+        self
+    }
+
+    // this skips a run until chat has had `count` messages since the last one
+    pub const fn min_messages(mut self, count: u64) -> Self {
+        self.min_messages = count;
+        self
+    }
+
+    const fn new(kind: Kind) -> Self {
+        Self {
+            kind,
+            jitter: Duration::ZERO,
+            live_only: false,
+            min_messages: 0,
+        }
+    }
+
+    fn next_delay(&self) -> Option<Duration> {
+        let delay = match &self.kind {
+            Kind::Every(period) => *period,
+            Kind::Cron(cron) => {
+                let now = OffsetDateTime::now_utc();
+                (cron.next_after(now)? - now).try_into().unwrap_or_default()
+            }
+        };
+
+        let jitter = self.jitter.as_millis().min(u64::MAX as u128) as u64;
+        Some(delay + Duration::from_millis(fastrand::u64(0..=jitter)))
+    }
+}
+
+// this is used for `Schedule::only_while_live`
+#[async_trait::async_trait]
+pub trait LiveCheck
+where
+    Self: Send + Sync + 'static,
+{
+    async fn is_live(&self) -> anyhow::Result<bool>;
+}
+
+pub type SharedLiveCheck = Arc<dyn LiveCheck>;
+
+// this counts the messages seen on every transport
+#[derive(Clone, Debug, Default)]
+pub struct Activity(Arc<AtomicU64>);
+
+impl Activity {
+    pub fn record(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// the output of a scheduled callable, each transport renders it for its own announce channels
+#[derive(Clone)]
+pub struct Announcement {
+    pub name: Arc<str>,
+    render: Arc<BoxedRender>,
+}
+
+impl Render for Announcement {
+    fn render(&self, flavor: RenderFlavor) -> Vec<Response> {
+        self.render.render(flavor)
+    }
+}
+
+#[derive(Clone)]
+pub struct Announcements {
+    tx: broadcast::Sender<Announcement>,
+}
+
+impl Default for Announcements {
+    fn default() -> Self {
+        Self {
+            tx: broadcast::channel(16).0,
+        }
+    }
+}
+
+impl Announcements {
+    pub fn subscribe(&self) -> broadcast::Receiver<Announcement> {
+        self.tx.subscribe()
+    }
+
+    pub fn announce(&self, name: &str, render: impl Render + 'static) {
+        let announcement = Announcement {
+            name: name.into(),
+            render: Arc::new(render.boxed()),
+        };
+        if self.tx.send(announcement).is_err() {
+            log::debug!("nothing is listening for announcements from {name}");
+        }
+    }
+}
+
+// this sends every announcement to `channels` on `transport`, using the sender it registered
+pub async fn deliver(state: GlobalState, transport: Transport, channels: Vec<String>) {
+    let mut announcements = state.get_or_default::<Announcements>().subscribe();
+    let outbox = state.get_or_default::<Outbox>();
+
+    loop {
+        let announcement = match announcements.recv().await {
+            Ok(announcement) => announcement,
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                log::warn!("{transport} skipped {n} announcements");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        let responses = announcement
+            .render(transport.flavor())
+            .into_iter()
+            .filter(|resp| match resp {
+                Response::Problem(problem) => {
+                    log::warn!("{} ran into a problem: {problem}", announcement.name);
+                    false
+                }
+                _ => true,
+            })
+            .collect::<Vec<_>>();
+
+        for channel in &channels {
+            let locale = Locale::for_channel(&state, channel);
+            if let Err(err) = outbox.send(transport, channel, &responses, &locale).await {
+                log::warn!("cannot announce in {channel} on {transport}: {err}")
+            }
+        }
+    }
+}
+
+pub(crate) async fn run(
+    name: String,
+    schedule: Schedule,
+    state: GlobalState,
+    tick: impl Fn() -> BoxedFuture<'static, BoxedRender> + Send + Sync + 'static,
+) {
+    let activity = state.get_or_default::<Activity>();
+    let announcements = state.get_or_default::<Announcements>();
+    let tick = Arc::new(tick);
+
+    let mut seen = activity.count();
+    loop {
+        let delay = match schedule.next_delay() {
+            Some(delay) => delay,
+            None => {
+                log::warn!("{name} will never run again");
+                break;
+            }
+        };
+        tokio::time::sleep(delay).await;
+
+        if schedule.live_only && !is_live(&state, &name).await {
+            continue;
+        }
+
+        let count = activity.count();
+        if count.saturating_sub(seen) < schedule.min_messages {
+            log::trace!("skipping {name}, chat has been quiet");
+            continue;
+        }
+        seen = count;
+
+        // a panic shouldn't stop the schedule
+        let handle = tokio::spawn({
+            let tick = Arc::clone(&tick);
+            async move { tick().await }
+        });
+        match handle.await {
+            // there's nothing to announce for timers that only do work
+            Ok(render) if is_empty(&render) => {}
+            Ok(render) => announcements.announce(&name, render),
+            Err(err) => log::error!("{name} failed: {err}"),
+        }
+    }
+}
+
+fn is_empty(render: &BoxedRender) -> bool {
+    [
+        RenderFlavor::Twitch,
+        RenderFlavor::Discord,
+        RenderFlavor::Plain,
+    ]
+    .into_iter()
+    .all(|flavor| render.render(flavor).is_empty())
+}
+
+async fn is_live(state: &GlobalState, name: &str) -> bool {
+    let check = match state.try_get_owned::<SharedLiveCheck>() {
+        Some(check) => check,
+        None => {
+            log::warn!("{name} only runs while live, but there is no way to check that");
+            return false;
+        }
+    };
+
+    match check.is_live().await {
+        Ok(live) => live,
+        Err(err) => {
+            log::warn!("cannot check if the stream is live: {err}");
+            false
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    // cron matches either day field when both are restricted
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    fn parse(expr: &str) -> anyhow::Result<Self> {
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        let [minute, hour, day, month, weekday] = <[&str; 5]>::try_from(fields)
+            .map_err(|_| anyhow::anyhow!("'{expr}' must have 5 fields"))?;
+
+        let mut weekdays = Self::field(weekday, 0, 7).context("day of week")?;
+        // both 0 and 7 are sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7)
+        }
+
+        let this = Self {
+            minutes: Self::field(minute, 0, 59).context("minute")?,
+            hours: Self::field(hour, 0, 23).context("hour")?,
+            days: Self::field(day, 1, 31).context("day of month")?,
+            months: Self::field(month, 1, 12).context("month")?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        };
+
+        this.next_after(OffsetDateTime::now_utc())
+            .with_context(|| format!("'{expr}' will never run"))?;
+        Ok(this)
+    }
+
+    fn field(field: &str, min: u8, max: u8) -> anyhow::Result<u64> {
+        let parse = |s: &str| -> anyhow::Result<u8> {
+            let n = s
+                .parse()
+                .with_context(|| format!("invalid number: '{s}'"))?;
+            anyhow::ensure!((min..=max).contains(&n), "{n} is not in {min}-{max}");
+            Ok(n)
+        };
+
+        let mut bits = 0;
+        for part in field.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, Some(step.parse::<u8>()?)),
+                None => (part, None),
+            };
+            anyhow::ensure!(step != Some(0), "a step cannot be 0");
+
+            let (start, end) = match range.split_once('-') {
+                _ if range == "*" => (min, max),
+                Some((start, end)) => (parse(start)?, parse(end)?),
+                // a single value with a step runs until the end of the range
+                None if step.is_some() => (parse(range)?, max),
+                None => (parse(range)?, parse(range)?),
+            };
+            anyhow::ensure!(start <= end, "{start}-{end} is backwards");
+
+            for n in (start..=end).step_by(step.unwrap_or(1) as usize) {
+                bits |= 1 << n
+            }
+        }
+        Ok(bits)
+    }
+
+    fn next_after(&self, after: OffsetDateTime) -> Option<OffsetDateTime> {
+        const fn has(bits: u64, n: u8) -> bool {
+            bits & (1 << n) != 0
+        }
+
+        let next_day = |t: OffsetDateTime| Some(t.date().next_day()?.midnight().assume_utc());
+
+        let mut t =
+            after.replace_second(0).ok()?.replace_nanosecond(0).ok()? + time::Duration::minutes(1);
+
+        // feb 30th would otherwise loop forever
+        let limit = after.year() + 5;
+        while t.year() <= limit {
+            let day = has(self.days, t.day());
+            let weekday = has(self.weekdays, t.weekday().number_days_from_sunday());
+            let day = match (self.any_day, self.any_weekday) {
+                (false, false) => day || weekday,
+                _ => day && weekday,
+            };
+
+            if !has(self.months, t.month() as u8) || !day {
+                t = next_day(t)?;
+                continue;
+            }
+
+            if !has(self.hours, t.hour()) {
+                t = t.replace_minute(0).ok()? + time::Duration::hours(1);
+                continue;
+            }
+
+            if !has(self.minutes, t.minute()) {
+                t += time::Duration::minutes(1);
+                continue;
+            }
+
+            return Some(t);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use time::{Date, Month};
+
+    use super::*;
+    use crate::{
+        callable::{Binding, IntoCallable},
+        state::State,
+    };
+
+    fn at(month: Month, day: u8, hour: u8, minute: u8) -> OffsetDateTime {
+        Date::from_calendar_date(2022, month, day)
+            .unwrap()
+            .with_hms(hour, minute, 0)
+            .unwrap()
+            .assume_utc()
+    }
+
+    fn next(expr: &str, after: OffsetDateTime) -> OffsetDateTime {
+        Cron::parse(expr).unwrap().next_after(after).unwrap()
+    }
+
+    #[test]
+    fn cron_fields() {
+        let after = at(Month::August, 20, 10, 7);
+        assert_eq!(next("*/15 * * * *", after), at(Month::August, 20, 10, 15));
+        assert_eq!(next("5,50 * * * *", after), at(Month::August, 20, 10, 50));
+        assert_eq!(next("0 9-17/4 * * *", after), at(Month::August, 20, 13, 0));
+        assert_eq!(next("0 0 1 * *", after), at(Month::September, 1, 0, 0));
+        assert_eq!(next("30 6 * 12 *", after), at(Month::December, 1, 6, 30));
+
+        // the next run is always after the given time
+        let after = at(Month::August, 20, 10, 15);
+        assert_eq!(next("*/15 * * * *", after), at(Month::August, 20, 10, 30));
+    }
+
+    #[test]
+    fn cron_weekdays() {
+        // this is a saturday
+        let after = at(Month::August, 20, 10, 0);
+        assert_eq!(next("0 9 * * 1", after), at(Month::August, 22, 9, 0));
+        assert_eq!(next("0 9 * * 0", after), at(Month::August, 21, 9, 0));
+        assert_eq!(next("0 9 * * 7", after), at(Month::August, 21, 9, 0));
+
+        // either day field matches when both are restricted
+        assert_eq!(next("0 0 25 * 1", after), at(Month::August, 22, 0, 0));
+        assert_eq!(next("0 0 21 * 5", after), at(Month::August, 21, 0, 0));
+    }
+
+    #[test]
+    fn cron_errors() {
+        for expr in [
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "10-5 * * * *",
+            "a * * * *",
+            "0 0 30 2 *",
+        ] {
+            assert!(Cron::parse(expr).is_err(), "{expr}");
+        }
+    }
+
+    struct Ticker(Arc<AtomicUsize>);
+
+    impl Ticker {
+        async fn tick(self: Arc<Self>, _: GlobalState) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timers_stop_with_their_binding() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let state = GlobalState::new(State::default());
+        let callable = Binding::create(state, Ticker(count.clone()))
+            .await
+            .every(Duration::from_secs(10), Ticker::tick)
+            .into_callable();
+
+        tokio::time::sleep(Duration::from_secs(25)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+
+        drop(callable);
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,85 +1,191 @@
 use anyhow::Context;
+use arc_swap::ArcSwapOption;
+use tokio::sync::watch;
 
 use std::{
     any::{Any, TypeId},
     collections::HashMap,
     path::PathBuf,
-    sync::Arc,
+    sync::{Arc, RwLock},
 };
-use tokio::sync::{RwLock, RwLockReadGuard};
 
-use crate::ConfigPath;
+use crate::{Clock, ConfigPath};
 
+// each value is an `Arc` snapshot, so nothing holds a lock while using one
 #[derive(Default, Clone)]
-pub struct GlobalState(pub(crate) Arc<RwLock<State>>);
+pub struct GlobalState(pub(crate) Arc<State>);
 
 impl GlobalState {
-    pub async fn get_config_path<C>(&self) -> PathBuf
+    pub fn get_config_path<C>(&self) -> anyhow::Result<PathBuf>
     where
         C: ConfigPath + Any + Send + Sync,
     {
-        self.get::<C>().await.file_path().to_path_buf()
+        self.0.get_config_path::<C>()
+    }
+
+    pub fn clock(&self) -> Clock {
+        self.try_get_owned().unwrap_or_default()
     }
 }
 
 impl GlobalState {
     pub fn new(state: State) -> Self {
-        Self(Arc::new(RwLock::new(state)))
+        Self(Arc::new(state))
     }
 
-    // TODO this should use try_ and unwrap
-    pub async fn get<T>(&self) -> RwLockReadGuard<'_, T>
+    pub fn get<T>(&self) -> anyhow::Result<Arc<T>>
     where
         T: Any + Send + Sync + 'static,
     {
-        RwLockReadGuard::map(self.0.read().await, |state| state.get::<T>().unwrap())
+        self.0.get()
     }
 
-    pub async fn get_owned<T>(&self) -> T
+    pub fn get_owned<T>(&self) -> anyhow::Result<T>
     where
         T: Any + Send + Sync + 'static,
         T: Clone,
     {
-        (&*self.get::<T>().await).clone()
+        self.get::<T>().map(|val| (*val).clone())
     }
 
-    pub async fn try_get_owned<T>(&self) -> Option<T>
+    pub fn try_get<T>(&self) -> Option<Arc<T>>
+    where
+        T: Any + Send + Sync + 'static,
+    {
+        self.0.try_get()
+    }
+
+    pub fn try_get_owned<T>(&self) -> Option<T>
     where
         T: Any + Send + Sync + 'static,
         T: Clone,
     {
-        Some((&*self.try_get::<T>().await?).clone())
+        self.try_get::<T>().map(|val| (*val).clone())
     }
 
-    pub async fn try_get<T>(&self) -> Option<RwLockReadGuard<'_, T>>
+    // this inserts the default value if it is missing, so everyone shares the same one
+    pub fn get_or_default<T>(&self) -> Arc<T>
     where
         T: Any + Send + Sync + 'static,
+        T: Default,
     {
-        RwLockReadGuard::try_map(self.0.read().await, |state| state.get::<T>().ok()).ok()
+        let slot = self.0.slot_or_insert::<T>();
+        if let Some(val) = slot.value.load_full() {
+            return val;
+        }
+
+        let val = Arc::new(T::default());
+        let prev = slot
+            .value
+            .compare_and_swap(&None::<Arc<T>>, Some(Arc::clone(&val)));
+        match &*prev {
+            // someone else inserted it first
+            Some(prev) => Arc::clone(prev),
+            None => {
+                slot.notify();
+                val
+            }
+        }
     }
 
-    pub async fn insert<T>(&self, val: T)
+    // readers that already have the old value keep it until they drop it
+    pub fn insert<T>(&self, val: T)
     where
         T: Any + Send + Sync + 'static,
     {
-        self.0.write().await.insert(val);
+        self.0.slot_or_insert::<T>().store(val);
+    }
+
+    // `update` can run more than once if another writer got there first, so it shouldn't have side effects
+    pub fn update<T, F>(&self, mut update: F) -> anyhow::Result<Arc<T>>
+    where
+        T: Any + Send + Sync + 'static,
+        T: Clone,
+        F: FnMut(&mut T),
+    {
+        let slot = self.0.get_slot::<T>()?;
+        let mut next = None;
+        slot.value.rcu(|current| {
+            next = current.as_ref().map(|current| {
+                let mut val = T::clone(current);
+                update(&mut val);
+                Arc::new(val)
+            });
+            next.clone()
+        });
+
+        let next =
+            next.with_context(|| anyhow::anyhow!("could not find {}", State::name_of::<T>()))?;
+        slot.notify();
+        Ok(next)
+    }
+
+    // this can be used before the value is inserted, the first insert is the first change
+    pub fn subscribe<T>(&self) -> Watch<T>
+    where
+        T: Any + Send + Sync + 'static,
+    {
+        Watch {
+            rx: self.0.slot_or_insert::<T>().changed.subscribe(),
+        }
+    }
+}
+
+pub struct Watch<T> {
+    rx: watch::Receiver<Option<Arc<T>>>,
+}
+
+impl<T> Watch<T> {
+    pub fn current(&self) -> Option<Arc<T>> {
+        self.rx.borrow().clone()
+    }
+
+    // this waits for the next value, `None` means the state is gone
+    pub async fn changed(&mut self) -> Option<Arc<T>> {
+        self.rx.changed().await.ok()?;
+        self.rx.borrow_and_update().clone()
+    }
+}
+
+// a slot is created the first time its type is used, and is never removed
+struct Slot<T> {
+    value: ArcSwapOption<T>,
+    // this is only for subscribers, readers use `value`
+    changed: watch::Sender<Option<Arc<T>>>,
+}
+
+impl<T> Slot<T> {
+    fn empty() -> Self {
+        Self {
+            value: ArcSwapOption::empty(),
+            changed: watch::channel(None).0,
+        }
+    }
+
+    fn store(&self, val: T) -> Option<Arc<T>> {
+        let prev = self.value.swap(Some(Arc::new(val)));
+        self.notify();
+        prev
+    }
+
+    // this sends whatever is newest, so racing writers can't leave an old value behind
+    fn notify(&self) {
+        self.changed
+            .send_modify(|val| *val = self.value.load_full())
     }
 }
 
 #[derive(Default, Debug)]
 pub struct State {
-    map: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    slots: RwLock<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
 }
 
 impl State {
-    pub fn get_config_path<C>(&self) -> PathBuf
+    pub fn get_config_path<C>(&self) -> anyhow::Result<PathBuf>
     where
         C: ConfigPath + Any + Send + Sync,
     {
-        self.get::<C>()
-            .expect("config path")
-            .file_path()
-            .to_path_buf()
+        Ok(self.get::<C>()?.file_path().to_path_buf())
     }
 }
 
@@ -88,29 +194,24 @@ impl State {
     where
         T: Any + Send + Sync + 'static,
     {
-        if let Some(..) = self.map.insert(TypeId::of::<T>(), Box::new(val)) {
-            log::warn!("replaced: {}", std::any::type_name::<T>());
+        if self.slot_or_insert::<T>().store(val).is_some() {
+            log::warn!("replaced: {}", Self::name_of::<T>());
         }
     }
 
-    pub fn get<T>(&self) -> anyhow::Result<&T>
+    pub fn get<T>(&self) -> anyhow::Result<Arc<T>>
     where
         T: Any + Send + Sync + 'static,
     {
-        self.map
-            .get(&TypeId::of::<T>())
-            .and_then(|c| c.downcast_ref())
+        self.try_get()
             .with_context(|| anyhow::anyhow!("could not find {}", Self::name_of::<T>()))
     }
 
-    pub fn get_mut<T>(&mut self) -> anyhow::Result<&mut T>
+    pub fn try_get<T>(&self) -> Option<Arc<T>>
     where
         T: Any + Send + Sync + 'static,
     {
-        self.map
-            .get_mut(&TypeId::of::<T>())
-            .and_then(|c| c.downcast_mut())
-            .with_context(|| anyhow::anyhow!("could not find {}", Self::name_of::<T>()))
+        self.slot::<T>()?.value.load_full()
     }
 
     pub fn extract<T, U, F>(&self, map: F) -> anyhow::Result<U>
@@ -119,10 +220,131 @@ impl State {
         U: 'static,
         F: FnOnce(&T) -> U,
     {
-        self.get::<T>().map(map)
+        self.get::<T>().map(|val| map(&val))
+    }
+
+    fn get_slot<T>(&self) -> anyhow::Result<Arc<Slot<T>>>
+    where
+        T: Any + Send + Sync + 'static,
+    {
+        self.slot()
+            .with_context(|| anyhow::anyhow!("could not find {}", Self::name_of::<T>()))
+    }
+
+    fn slot<T>(&self) -> Option<Arc<Slot<T>>>
+    where
+        T: Any + Send + Sync + 'static,
+    {
+        let slot = self.slots.read().unwrap().get(&TypeId::of::<T>())?.clone();
+        Some(slot.downcast().expect("slot type"))
+    }
+
+    fn slot_or_insert<T>(&self) -> Arc<Slot<T>>
+    where
+        T: Any + Send + Sync + 'static,
+    {
+        if let Some(slot) = self.slot::<T>() {
+            return slot;
+        }
+
+        let slot = Arc::clone(
+            self.slots
+                .write()
+                .unwrap()
+                .entry(TypeId::of::<T>())
+                .or_insert_with(|| Arc::new(Slot::<T>::empty())),
+        );
+        slot.downcast().expect("slot type")
     }
 
     fn name_of<T: 'static>() -> &'static str {
         std::any::type_name::<T>()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn concurrent_get_or_default() {
+        static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+        struct Counter;
+        impl Default for Counter {
+            fn default() -> Self {
+                CREATED.fetch_add(1, Ordering::SeqCst);
+                Self
+            }
+        }
+
+        let state = GlobalState::default();
+        let barrier = Arc::new(std::sync::Barrier::new(8));
+        let values = (0..8)
+            .map(|_| {
+                let (state, barrier) = (state.clone(), barrier.clone());
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    state.get_or_default::<Counter>()
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+
+        // more than one default can be made, but only one is kept
+        let kept = state.get::<Counter>().unwrap();
+        assert!(values.iter().all(|val| Arc::ptr_eq(val, &kept)));
+        assert!(CREATED.load(Ordering::SeqCst) >= 1);
+    }
+
+    #[test]
+    fn update_missing() {
+        let state = GlobalState::default();
+        assert!(state.update::<String, _>(|s| s.push('!')).is_err());
+
+        // a subscriber makes the slot, but there still isn't a value
+        let _watch = state.subscribe::<String>();
+        assert!(state.update::<String, _>(|s| s.push('!')).is_err());
+        assert!(state.try_get::<String>().is_none());
+
+        state.insert(String::from("hello"));
+        let val = state.update::<String, _>(|s| s.push('!')).unwrap();
+        assert_eq!(*val, "hello!");
+        assert_eq!(*state.get::<String>().unwrap(), "hello!");
+    }
+
+    #[test]
+    fn insert_keeps_held_values() {
+        let state = GlobalState::default();
+        state.insert(vec![1, 2, 3]);
+
+        let held = state.get::<Vec<i32>>().unwrap();
+        state.insert(vec![4]);
+        state.update::<Vec<i32>, _>(|v| v.push(5)).unwrap();
+
+        assert_eq!(*held, [1, 2, 3]);
+        assert_eq!(*state.get::<Vec<i32>>().unwrap(), [4, 5]);
+    }
+
+    #[tokio::test]
+    async fn subscribers_see_changes() {
+        let state = GlobalState::default();
+        let mut watch = state.subscribe::<String>();
+        assert!(watch.current().is_none());
+
+        state.insert(String::from("hello"));
+        assert_eq!(*watch.changed().await.unwrap(), "hello");
+
+        state.update::<String, _>(|s| s.push('!')).unwrap();
+        assert_eq!(*watch.changed().await.unwrap(), "hello!");
+        assert_eq!(*watch.current().unwrap(), "hello!");
+
+        // a late subscriber starts with the current value
+        let late = state.subscribe::<String>();
+        assert_eq!(*late.current().unwrap(), "hello!");
+    }
+}
@@ -0,0 +1,493 @@
+use std::{borrow::Cow, future::Future, sync::Arc, time::Duration};
+
+use crate::{
+    callable::CallableFn,
+    message::MessageType,
+    prelude::{
+        GlobalState, Message, Prefix, Render, Response, SharedCallable, SharedRegistry, State,
+    },
+    render::{BoxedRender, RenderFlavor},
+    BoxedFuture, Clock,
+};
+
+mod stand_in;
+pub use stand_in::{Canned, Recorded, StandIn, StandInBuilder};
+
+mod transcript;
+pub use transcript::{run_transcript, Badge, Step, Transcript, BLESS_VAR};
+
+#[async_trait::async_trait]
+pub trait Mock
+where
+    Self: Sized + Send + Sync + 'static,
+{
+    async fn mock(self) -> TestBinding;
+    async fn mock_with_state(self, state: State) -> TestBinding;
+}
+
+#[async_trait::async_trait]
+impl<F, Fut, C> Mock for F
+where
+    F: Fn(GlobalState) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = anyhow::Result<C>> + Send,
+    C: CallableFn<Out = BoxedFuture<'static, BoxedRender>>,
+{
+    async fn mock(self) -> TestBinding {
+        Self::mock_with_state(self, <_>::default()).await
+    }
+
+    // a registry already in the state is kept, otherwise an empty one is used
+    async fn mock_with_state(self, mut state: State) -> TestBinding {
+        if state.try_get::<SharedRegistry>().is_none() {
+            state.insert(SharedRegistry::default());
+        }
+        state.insert(Clock::default());
+
+        let state = GlobalState::new(state);
+        let callable = (self)(state.clone()).await.expect("valid binding");
+
+        TestBinding {
+            callable: Arc::new(callable),
+            responses: Vec::new(),
+            channel: Cow::from("#test_chanenl"),
+            sender: Cow::from("test_user"),
+            state,
+            moderator: false,
+            admin: false,
+            prefix: Prefix::default(),
+        }
+    }
+}
+
+pub struct TestBinding {
+    callable: SharedCallable,
+    responses: Vec<Response>,
+    channel: Cow<'static, str>,
+    sender: Cow<'static, str>,
+    state: GlobalState,
+    moderator: bool,
+    admin: bool,
+    prefix: Prefix,
+}
+
+impl TestBinding {
+    pub fn with_moderator(mut self) -> Self {
+        self.moderator = true;
+        self
+    }
+
+    pub fn with_admin(mut self) -> Self {
+        self.admin = true;
+        self
+    }
+
+    pub fn with_sender(mut self, sender: &str) -> Self {
+        self.sender = sender.to_string().into();
+        self
+    }
+
+    pub fn with_channel(mut self, channel: &str) -> Self {
+        self.channel = channel.to_string().into();
+        self
+    }
+
+    pub fn with_prefix(mut self, prefix: Prefix) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    pub fn advance_time(&self, dur: Duration) {
+        self.state.clock().advance(dur)
+    }
+
+    pub fn get_response(&mut self) -> Vec<Response> {
+        std::mem::take(&mut self.responses)
+    }
+
+    pub async fn send_message<B: BuildTestMessage + Send>(&mut self, data: &str) {
+        let builder = self.builder::<B>(data);
+        self.send(builder).await
+    }
+
+    fn builder<B: BuildTestMessage>(&self, data: &str) -> B {
+        let mut builder = B::default()
+            .with_data(data)
+            .with_channel(&self.channel)
+            .with_sender(&self.sender);
+
+        if self.admin {
+            builder = builder.with_admin()
+        }
+        if self.moderator {
+            builder = builder.with_moderator()
+        }
+        builder
+    }
+
+    async fn send<B: BuildTestMessage + Send>(&mut self, builder: B) {
+        let flavor = builder.render_flavor();
+        let prefixes = self.prefix.resolve(&self.channel, &[]);
+        let msg = Message::new(builder.into_message(), self.state.clone()).with_prefixes(prefixes);
+        let out = self.callable.call(msg).await.render(flavor);
+        self.responses.extend(out);
+    }
+}
+
+pub trait BuildTestMessage
+where
+    Self: Sized + Default,
+{
+    type Output: MessageType;
+    fn into_message(self) -> Self::Output;
+
+    fn with_admin(self) -> Self {
+        self
+    }
+
+    fn with_moderator(self) -> Self {
+        self
+    }
+
+    fn with_vip(self) -> Self {
+        self
+    }
+
+    fn with_subscriber(self) -> Self {
+        self
+    }
+
+    fn with_sender(self, _: &str) -> Self {
+        self
+    }
+
+    fn with_channel(self, _: &str) -> Self {
+        self
+    }
+
+    fn with_data(self, _: &str) -> Self {
+        self
+    }
+
+    fn with_flavor(self, _: RenderFlavor) -> Self {
+        self
+    }
+
+    fn render_flavor(&self) -> RenderFlavor {
+        RenderFlavor::Twitch
+    }
+}
+
+pub struct MockMessage {
+    source: String,
+    sender: String,
+    data: String,
+    admin: bool,
+    moderator: bool,
+    vip: bool,
+    subscriber: bool,
+    flavor: RenderFlavor,
+}
+
+impl Default for MockMessage {
+    fn default() -> Self {
+        Self {
+            source: Default::default(),
+            sender: Default::default(),
+            data: Default::default(),
+            admin: Default::default(),
+            moderator: Default::default(),
+            vip: Default::default(),
+            subscriber: Default::default(),
+            flavor: RenderFlavor::Twitch,
+        }
+    }
+}
+
+impl BuildTestMessage for MockMessage {
+    type Output = Self;
+
+    fn into_message(self) -> Self::Output {
+        self
+    }
+
+    fn with_admin(mut self) -> Self {
+        self.admin = true;
+        self
+    }
+
+    fn with_moderator(mut self) -> Self {
+        self.moderator = true;
+        self
+    }
+
+    fn with_vip(mut self) -> Self {
+        self.vip = true;
+        self
+    }
+
+    fn with_subscriber(mut self) -> Self {
+        self.subscriber = true;
+        self
+    }
+
+    fn with_sender(mut self, sender: &str) -> Self {
+        self.sender = sender.into();
+        self
+    }
+
+    fn with_channel(mut self, channel: &str) -> Self {
+        self.source = channel.into();
+        self
+    }
+
+    fn with_data(mut self, data: &str) -> Self {
+        self.data = data.into();
+        self
+    }
+
+    fn with_flavor(mut self, flavor: RenderFlavor) -> Self {
+        self.flavor = flavor;
+        self
+    }
+
+    fn render_flavor(&self) -> RenderFlavor {
+        self.flavor
+    }
+}
+
+impl MessageType for MockMessage {
+    fn data(&self) -> &str {
+        &self.data
+    }
+
+    fn sender_name(&self) -> &str {
+        &self.sender
+    }
+
+    fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn is_from_admin(&self) -> bool {
+        self.admin
+    }
+
+    fn is_from_moderator(&self) -> bool {
+        self.moderator
+    }
+
+    fn is_from_vip(&self) -> bool {
+        self.vip
+    }
+
+    fn is_from_subscriber(&self) -> bool {
+        self.subscriber
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::callable::{Binding, Command, Dispatch, IntoCallable};
+
+    struct Handlers;
+
+    impl Handlers {
+        async fn slow(self: Arc<Self>, _: Message) -> &'static str {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            "too late"
+        }
+
+        async fn crash(self: Arc<Self>, _: Message) -> &'static str {
+            panic!("this is a test")
+        }
+
+        async fn listen(self: Arc<Self>, _: Message) -> &'static str {
+            "heard"
+        }
+
+        async fn echo(self: Arc<Self>, msg: Message) -> String {
+            msg.args().get("text").unwrap().to_string()
+        }
+    }
+
+    async fn dispatch(data: &str, report_failures: bool) -> Vec<Response> {
+        let mut state = State::default();
+        state.insert(crate::prelude::SharedRegistry::default());
+        let state = GlobalState::new(state);
+
+        let callables = [
+            Binding::create(state.clone(), Handlers)
+                .await
+                .bind_cmd(Command::new("!slow"), Handlers::slow)
+                .into_callable(),
+            Binding::create(state.clone(), Handlers)
+                .await
+                .bind_cmd(Command::new("!crash"), Handlers::crash)
+                .into_callable(),
+            Binding::create(state.clone(), Handlers)
+                .await
+                .listen(Handlers::listen)
+                .into_callable(),
+        ];
+
+        let msg = Message::new(MockMessage::default().with_data(data), state);
+        Dispatch::new(&callables)
+            .with_deadline(Duration::from_millis(50))
+            .report_failures(report_failures)
+            .into_render(&msg)
+            .await
+            .render(RenderFlavor::Twitch)
+    }
+
+    fn problems(responses: &[Response]) -> Vec<&str> {
+        responses
+            .iter()
+            .filter_map(|resp| match resp {
+                Response::Problem(problem) => Some(&**problem),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn timeout_is_reported() {
+        let responses = dispatch("!slow", true).await;
+        assert_eq!(problems(&responses), ["!slow took too long"]);
+        assert!(matches!(&responses[1], Response::Say(s) if s == "heard"));
+    }
+
+    #[tokio::test]
+    async fn panic_is_reported() {
+        let responses = dispatch("!crash", true).await;
+        assert_eq!(problems(&responses), ["!crash crashed"]);
+        assert!(matches!(&responses[1], Response::Say(s) if s == "heard"));
+    }
+
+    #[tokio::test]
+    async fn failures_can_be_silent() {
+        let responses = dispatch("!crash", false).await;
+        assert!(problems(&responses).is_empty());
+        assert_eq!(responses.len(), 1);
+    }
+
+    // each callable in a binding is guarded on its own
+    #[tokio::test]
+    async fn failures_inside_of_a_binding() {
+        let state = GlobalState::new(State::default());
+        let callables = [Binding::create(state.clone(), Handlers)
+            .await
+            .bind_cmd(Command::new("!hi"), Handlers::listen)
+            .bind_cmd(Command::new("!slow"), Handlers::slow)
+            .listen(Handlers::crash)
+            .into_callable()];
+
+        let dispatch = |data: &str| {
+            let msg = Message::new(MockMessage::default().with_data(data), state.clone());
+            let callables = &callables;
+            async move {
+                Dispatch::new(callables)
+                    .with_deadline(Duration::from_millis(50))
+                    .report_failures(true)
+                    .into_render(&msg)
+                    .await
+                    .render(RenderFlavor::Twitch)
+            }
+        };
+
+        let responses = dispatch("!hi").await;
+        assert_eq!(problems(&responses), ["a listener crashed"]);
+        assert!(matches!(&responses[0], Response::Say(s) if s == "heard"));
+
+        let responses = dispatch("!slow").await;
+        assert_eq!(
+            problems(&responses),
+            ["!slow took too long", "a listener crashed"]
+        );
+    }
+
+    #[tokio::test]
+    async fn bindings_are_checked() {
+        let mut registry = crate::help::Registry::default();
+        registry.add("handlers", Command::new("!slow"));
+        registry.add("handlers", Command::new("!gone"));
+
+        let mut state = State::default();
+        state.insert(SharedRegistry::new(registry.clone()));
+        let state = GlobalState::new(state);
+
+        let callables = [
+            Binding::create(state.clone(), Handlers)
+                .await
+                .bind_cmd(Command::new("!slow"), Handlers::slow)
+                .bind_cmd(Command::new("!crash").alias("!boom"), Handlers::crash)
+                .into_callable(),
+            Binding::create(state.clone(), Handlers)
+                .await
+                .bind_cmd(Command::new("!boom"), Handlers::crash)
+                .listen(Handlers::listen)
+                .into_callable(),
+        ];
+
+        let report = registry.check_bindings(&callables);
+        assert_eq!(report.unbound, ["handlers::!gone"]);
+        assert_eq!(report.unregistered, ["handlers::!boom", "handlers::!crash"]);
+        assert_eq!(
+            report.duplicates,
+            ["!boom is used by handlers::!crash, handlers::!boom"]
+        );
+
+        let live = crate::help::Registry::from_bindings(&callables);
+        let mut names = live
+            .descriptions_for("handlers")
+            .unwrap()
+            .command_names()
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        assert_eq!(names, ["!boom", "!boom", "!crash", "!slow"]);
+    }
+
+    // the registry can rename and restrict a command, but the handler still gets the arguments it declared
+    #[tokio::test]
+    async fn registry_keeps_the_declared_usage() {
+        let mut registry = crate::help::Registry::default();
+        registry.add(
+            "handlers",
+            Command::new("!echo")
+                .alias("!say")
+                .help("repeats something")
+                .usage("<other>")
+                .unwrap()
+                .permission(crate::callable::Permission::Moderator),
+        );
+        let mut state = State::default();
+        state.insert(SharedRegistry::new(registry));
+
+        let bind = |state: GlobalState| async move {
+            anyhow::Result::<SharedCallable>::Ok(
+                Binding::create(state, Handlers)
+                    .await
+                    .bind_cmd(
+                        Command::new("!echo").usage("<text..>").unwrap(),
+                        Handlers::echo,
+                    )
+                    .into_callable(),
+            )
+        };
+        let mut binding = bind.mock_with_state(state).await;
+
+        binding.send_message::<MockMessage>("!say hello").await;
+        assert!(matches!(&*binding.get_response(), [Response::Problem(..)]));
+
+        let mut binding = binding.with_moderator();
+        binding
+            .send_message::<MockMessage>("!say hello world")
+            .await;
+        assert_eq!(
+            binding.get_response(),
+            [Response::Say(String::from("hello world"))]
+        );
+    }
+}
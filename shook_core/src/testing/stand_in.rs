@@ -0,0 +1,190 @@
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    body::Bytes,
+    http::{header::CONTENT_TYPE, HeaderMap, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    routing::any,
+    Router, Server,
+};
+use tokio::sync::oneshot;
+
+// an in-process http server with canned responses, so a client can be pointed at it instead of the real service.
+// it stops when dropped
+pub struct StandIn {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<Recorded>>>,
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl StandIn {
+    pub fn builder() -> StandInBuilder {
+        StandInBuilder::default()
+    }
+
+    // this has no trailing slash, e.g. `http://127.0.0.1:1234`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    // every request so far, including ones that had no canned response
+    pub fn requests(&self) -> Vec<Recorded> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[derive(Default)]
+pub struct StandInBuilder {
+    routes: HashMap<(String, String), Canned>,
+}
+
+impl StandInBuilder {
+    pub fn get(self, path: &str, canned: Canned) -> Self {
+        self.route("GET", path, canned)
+    }
+
+    pub fn post(self, path: &str, canned: Canned) -> Self {
+        self.route("POST", path, canned)
+    }
+
+    // the path is matched exactly, without the query
+    pub fn route(mut self, method: &str, path: &str, canned: Canned) -> Self {
+        self.routes
+            .insert((method.to_ascii_uppercase(), path.to_string()), canned);
+        self
+    }
+
+    pub async fn start(self) -> anyhow::Result<StandIn> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+
+        let routes = Arc::new(self.routes);
+        let requests = <Arc<Mutex<Vec<_>>>>::default();
+
+        let app = Router::new().fallback(any({
+            let requests = requests.clone();
+            move |method, uri, headers, body| {
+                respond(routes.clone(), requests.clone(), method, uri, headers, body)
+            }
+        }));
+
+        let (shutdown, done) = oneshot::channel();
+        let server = Server::from_tcp(listener)?
+            .serve(app.into_make_service())
+            .with_graceful_shutdown(async move {
+                let _ = done.await;
+            });
+
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                log::warn!("stand-in server on {addr} stopped: {err}")
+            }
+        });
+
+        Ok(StandIn {
+            addr,
+            requests,
+            _shutdown: shutdown,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Canned {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Canned {
+    pub fn json(body: &impl serde::Serialize) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: serde_json::to_string(body).expect("serializable body"),
+        }
+    }
+
+    pub fn text(body: impl Into<String>) -> Self {
+        Self {
+            status: 200,
+            content_type: "text/plain",
+            body: body.into(),
+        }
+    }
+
+    // an empty body with this status
+    pub fn status(status: u16) -> Self {
+        Self::text("").with_status(status)
+    }
+
+    pub const fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Recorded {
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Recorded {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, val)| &**val)
+    }
+}
+
+async fn respond(
+    routes: Arc<HashMap<(String, String), Canned>>,
+    requests: Arc<Mutex<Vec<Recorded>>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let key = (method.to_string(), uri.path().to_string());
+    requests.lock().unwrap().push(Recorded {
+        method: key.0.clone(),
+        path: key.1.clone(),
+        query: uri.query().map(ToString::to_string),
+        headers: headers
+            .iter()
+            .map(|(k, v)| {
+                (
+                    k.to_string(),
+                    String::from_utf8_lossy(v.as_bytes()).to_string(),
+                )
+            })
+            .collect(),
+        body: String::from_utf8_lossy(&body).to_string(),
+    });
+
+    let canned = match routes.get(&key) {
+        Some(canned) => canned,
+        None => {
+            let body = format!("no canned response for {} {}", key.0, key.1);
+            return (StatusCode::NOT_FOUND, body).into_response();
+        }
+    };
+
+    let status = StatusCode::from_u16(canned.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    (
+        status,
+        [(CONTENT_TYPE, canned.content_type)],
+        canned.body.clone(),
+    )
+        .into_response()
+}
@@ -0,0 +1,247 @@
+use std::{fmt::Write as _, path::Path, time::Duration};
+
+use anyhow::Context;
+
+use super::{BuildTestMessage, TestBinding};
+use crate::{prelude::Response, render::RenderFlavor};
+
+// when this is set, `run_transcript` writes what was actually said back into the file
+pub const BLESS_VAR: &str = "SHOOK_BLESS";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Transcript {
+    // these are used for every step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+
+    pub steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Step {
+    pub input: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub badges: Vec<Badge>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flavor: Option<RenderFlavor>,
+    // the clock is moved forward by this many seconds before the input is sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub advance: Option<u64>,
+
+    #[serde(default)]
+    pub expect: Vec<Response>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Badge {
+    Broadcaster,
+    Moderator,
+    Vip,
+    Subscriber,
+}
+
+impl Transcript {
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        serde_yaml::from_str(&data)
+            .with_context(|| format!("invalid transcript: {}", path.display()))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, serde_yaml::to_string(self)?)
+            .with_context(|| format!("cannot write {}", path.display()))
+    }
+
+    // this sends each step in order. with `bless`, the expectations are replaced with what was said,
+    // otherwise every step that didn't match is reported at once
+    pub async fn replay<B>(&mut self, binding: &mut TestBinding, bless: bool) -> anyhow::Result<()>
+    where
+        B: BuildTestMessage + Send,
+    {
+        if let Some(channel) = &self.channel {
+            binding.channel = channel.clone().into();
+        }
+        if let Some(sender) = &self.sender {
+            binding.sender = sender.clone().into();
+        }
+
+        let mut mismatches = String::new();
+        for (i, step) in self.steps.iter_mut().enumerate() {
+            if let Some(secs) = step.advance {
+                binding.advance_time(Duration::from_secs(secs));
+            }
+
+            binding.send(step.builder::<B>(binding)).await;
+            let actual = binding.get_response();
+
+            if bless {
+                step.expect = actual;
+                continue;
+            }
+
+            if step.expect != actual {
+                let _ = writeln!(
+                    &mut mismatches,
+                    "step {} ({}):\nexpected:\n{}got:\n{}",
+                    i + 1,
+                    step.input,
+                    serde_yaml::to_string(&step.expect)?,
+                    serde_yaml::to_string(&actual)?,
+                );
+            }
+        }
+
+        anyhow::ensure!(mismatches.is_empty(), "{mismatches}");
+        Ok(())
+    }
+}
+
+impl Step {
+    fn builder<B: BuildTestMessage>(&self, binding: &TestBinding) -> B {
+        let mut builder = binding.builder::<B>(&self.input);
+        if let Some(sender) = &self.sender {
+            builder = builder.with_sender(sender)
+        }
+        if let Some(flavor) = self.flavor {
+            builder = builder.with_flavor(flavor)
+        }
+
+        for badge in &self.badges {
+            builder = match badge {
+                Badge::Broadcaster => builder.with_admin(),
+                Badge::Moderator => builder.with_moderator(),
+                Badge::Vip => builder.with_vip(),
+                Badge::Subscriber => builder.with_subscriber(),
+            }
+        }
+        builder
+    }
+}
+
+// this replays the transcript at `path` against `binding`. with `SHOOK_BLESS` set, the file is rewritten instead
+pub async fn run_transcript<B>(
+    mut binding: TestBinding,
+    path: impl AsRef<Path>,
+) -> anyhow::Result<()>
+where
+    B: BuildTestMessage + Send,
+{
+    let path = path.as_ref();
+    let mut transcript = Transcript::load(path)?;
+    let bless = std::env::var_os(BLESS_VAR).is_some();
+
+    transcript
+        .replay::<B>(&mut binding, bless)
+        .await
+        .with_context(|| format!("transcript {} did not match", path.display()))?;
+
+    if bless {
+        transcript.save(path)?;
+        log::info!("blessed {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        callable::{Binding, Command, IntoCallable},
+        prelude::{GlobalState, Message},
+        testing::{Mock, MockMessage},
+    };
+
+    struct Greeter;
+
+    impl Greeter {
+        async fn hello(self: Arc<Self>, msg: Message) -> String {
+            format!("hello, {}", msg.sender_name())
+        }
+
+        async fn secret(self: Arc<Self>, msg: Message) -> anyhow::Result<&'static str> {
+            msg.require_moderator()?;
+            Ok("the secret")
+        }
+    }
+
+    async fn greeter(state: GlobalState) -> anyhow::Result<crate::prelude::SharedCallable> {
+        Ok(Binding::create(state, Greeter)
+            .await
+            .bind_cmd(Command::new("!hello"), Greeter::hello)
+            .bind_cmd(Command::new("!secret"), Greeter::secret)
+            .into_callable())
+    }
+
+    const TRANSCRIPT: &str = r#"
+sender: alice
+steps:
+  - input: "!hello"
+    expect:
+      - kind: say
+        text: hello, alice
+  - input: "!secret"
+    sender: bob
+    badges: [moderator]
+    expect:
+      - kind: say
+        text: the secret
+  - input: not a command
+"#;
+
+    #[tokio::test]
+    async fn replay() {
+        let mut transcript: Transcript = serde_yaml::from_str(TRANSCRIPT).unwrap();
+        let mut binding = greeter.mock().await;
+        transcript
+            .replay::<MockMessage>(&mut binding, false)
+            .await
+            .unwrap();
+    }
+
+    // a badge the binding already gives is not taken away again
+    #[tokio::test]
+    async fn badges_are_kept() {
+        let mut transcript: Transcript = serde_yaml::from_str(TRANSCRIPT).unwrap();
+        let mut binding = greeter.mock().await.with_moderator();
+        transcript
+            .replay::<MockMessage>(&mut binding, false)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn mismatches_are_reported() {
+        let mut transcript: Transcript = serde_yaml::from_str(TRANSCRIPT).unwrap();
+        transcript.steps[0].expect = vec![Response::Say(String::from("goodbye"))];
+
+        let mut binding = greeter.mock().await;
+        let err = transcript
+            .replay::<MockMessage>(&mut binding, false)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("step 1 (!hello)"), "{err}");
+        assert!(!err.contains("step 2"), "{err}");
+
+        let mut binding = greeter.mock().await;
+        transcript
+            .replay::<MockMessage>(&mut binding, true)
+            .await
+            .unwrap();
+        assert_eq!(
+            transcript.steps[0].expect,
+            [Response::Say(String::from("hello, alice"))]
+        );
+    }
+}
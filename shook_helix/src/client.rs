@@ -80,3 +80,55 @@ impl HelixClient {
         Ok(response.json().await?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use shook_core::testing::{Canned, StandIn};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn get_streams() {
+        let body = serde_json::json!({
+            "data": [{
+                "id": "1",
+                "user_id": "2",
+                "user_name": "museun",
+                "game_id": "3",
+                "title": "a stream",
+                "viewer_count": 4,
+                "started_at": "2022-08-20T12:00:00Z",
+            }]
+        });
+        let server = StandIn::builder()
+            .get("/streams", Canned::json(&body))
+            .start()
+            .await
+            .unwrap();
+
+        let client = HelixClient::new_with_ep(server.url(), "client", "Bearer token");
+        let streams = client.get_streams(["museun"]).await.unwrap();
+        assert_eq!(streams.len(), 1);
+        assert_eq!(streams[0].user_name, "museun");
+        assert_eq!(streams[0].started_at.unix_timestamp(), 1_660_996_800);
+
+        let req = &server.requests()[0];
+        assert_eq!(req.query.as_deref(), Some("user_login=museun"));
+        assert_eq!(req.header("client-id"), Some("client"));
+        assert_eq!(req.header("authorization"), Some("Bearer token"));
+    }
+
+    #[tokio::test]
+    async fn errors() {
+        let body = serde_json::json!({ "error": "Unauthorized", "status": 401 });
+        let server = StandIn::builder()
+            .get("/streams", Canned::json(&body).with_status(401))
+            .start()
+            .await
+            .unwrap();
+
+        let client = HelixClient::new_with_ep(server.url(), "client", "Bearer token");
+        assert!(client.get_streams(["museun"]).await.is_err());
+        assert!(client.get_global_emotes().await.is_err());
+    }
+}
@@ -4,4 +4,7 @@ use shook_config::{Ephemeral, Secret};
 pub struct Config {
     pub client_id: Secret,
     pub client_secret: Ephemeral,
+    // this defaults to https://api.twitch.tv/helix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
 }
@@ -4,6 +4,9 @@ pub use oauth::OAuth;
 mod client;
 pub use client::HelixClient;
 
+mod live;
+pub use live::LiveStream;
+
 pub mod data;
 
 mod emote_map;
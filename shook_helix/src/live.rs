@@ -0,0 +1,26 @@
+use shook_core::schedule::LiveCheck;
+
+use crate::HelixClient;
+
+// this checks whether `streamer` is live, for schedules that only run while live
+pub struct LiveStream {
+    client: HelixClient,
+    streamer: String,
+}
+
+impl LiveStream {
+    pub fn new(client: HelixClient, streamer: impl ToString) -> Self {
+        Self {
+            client,
+            streamer: streamer.to_string(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LiveCheck for LiveStream {
+    async fn is_live(&self) -> anyhow::Result<bool> {
+        let streams = self.client.get_streams([&self.streamer]).await?;
+        Ok(!streams.is_empty())
+    }
+}
@@ -14,18 +14,27 @@ pub struct OAuth {
 
 impl OAuth {
     pub async fn create(client_id: &str, client_secret: &str) -> anyhow::Result<Self> {
+        Self::create_with_ep(Option::<String>::None, client_id, client_secret).await
+    }
+
+    pub async fn create_with_ep(
+        ep: impl Into<Option<String>> + Send,
+        client_id: &str,
+        client_secret: &str,
+    ) -> anyhow::Result<Self> {
+        const BASE_URL: &str = "https://id.twitch.tv/oauth2";
+
         assert!(!client_id.is_empty(), "client_id cannot be empty");
         assert!(!client_secret.is_empty(), "client_secret cannot be empty");
 
-        let req = reqwest::Client::new()
-            .post("https://id.twitch.tv/oauth2/token")
-            .query(&[
-                ("client_id", client_id),
-                ("client_secret", client_secret),
-                ("grant_type", "client_credentials"),
-            ]);
+        let url = format!("{}/token", ep.into().as_deref().unwrap_or(BASE_URL));
+        let req = reqwest::Client::new().post(&url).query(&[
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+            ("grant_type", "client_credentials"),
+        ]);
 
-        let resp = req.send().await?.json().await;
+        let resp = req.send().await?.error_for_status()?.json().await;
         Ok(resp.map(|this: Self| Self {
             client_id: client_id.to_string(),
             bearer_token: format!("Bearer {}", this.access_token),
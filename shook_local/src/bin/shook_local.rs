@@ -45,6 +45,11 @@ async fn main() -> anyhow::Result<()> {
 
             match serde_json::from_str(&resp).expect("valid json") {
                 Line::Message { data } => eprintln!("{data}"),
+                Line::Action { data } => eprintln!("action -> {data}"),
+                Line::Private { data } => eprintln!("private -> {data}"),
+                Line::Embed { embed } => eprintln!("embed -> {}", embed.to_text()),
+                Line::React { emoji } => eprintln!("react -> {emoji}"),
+                Line::Delete => eprintln!("delete"),
                 Line::None => break 'inner,
             }
         }
@@ -56,5 +61,10 @@ async fn main() -> anyhow::Result<()> {
 #[derive(Debug, serde::Deserialize)]
 enum Line {
     Message { data: String },
+    Action { data: String },
+    Private { data: String },
+    Embed { embed: shook_core::render::Embed },
+    React { emoji: String },
+    Delete,
     None,
 }
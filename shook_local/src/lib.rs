@@ -1,8 +1,10 @@
 use std::{net::SocketAddr, sync::Arc};
 
 use shook_core::{
+    outbox::{Outbox, Sender, Transport},
     prelude::{GlobalState, Message, SharedCallable},
-    render::{dispatch_and_render, RenderFlavor, Response},
+    render::{dispatch_and_render, Embed, RenderFlavor, Response},
+    schedule,
 };
 
 use shook_twitch as twitch;
@@ -10,8 +12,12 @@ use shook_twitch as twitch;
 use tokio::{
     io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader},
     net::{TcpListener, TcpStream},
+    sync::broadcast,
 };
 
+// every client talks in this channel, as the broadcaster
+const CHANNEL: &str = "#museun";
+
 #[derive(Debug, Clone)]
 pub struct LocalPort(SocketAddr);
 
@@ -28,28 +34,70 @@ pub async fn create_bot<const N: usize>(
     let listener = TcpListener::bind("localhost:0").await?;
     let addr = listener.local_addr()?;
     log::info!("local server is listening on: {addr}");
-    state.insert(LocalPort(addr)).await;
+    state.insert(LocalPort(addr));
+
+    let (tx, _) = broadcast::channel(16);
+    let outbox = state.get_or_default::<Outbox>();
+    outbox.register(Transport::Local, LocalSender { tx: tx.clone() });
+
+    let announce = schedule::deliver(state.clone(), Transport::Local, vec![CHANNEL.to_string()]);
+    tokio::spawn(announce);
 
     loop {
         if let Ok((client, addr)) = listener.accept().await {
             log::debug!("got client: {addr}");
-            tokio::spawn(handle(client, state.clone(), handlers.clone()));
+            let outgoing = tx.subscribe();
+            tokio::spawn(handle(client, state.clone(), handlers.clone(), outgoing));
         }
     }
 }
 
+// every connected client sees what is sent to any channel
+struct LocalSender {
+    tx: broadcast::Sender<Line>,
+}
+
+#[async_trait::async_trait]
+impl Sender for LocalSender {
+    async fn send(&self, channel: &str, responses: Vec<Response>) -> anyhow::Result<()> {
+        for resp in responses {
+            let line = match Line::from(resp) {
+                Line::Message { data } => Line::Message {
+                    data: format!("[{channel}] {data}"),
+                },
+                line => line,
+            };
+            // this is fine if no one is connected
+            let _ = self.tx.send(line);
+        }
+        Ok(())
+    }
+}
+
 async fn handle<const N: usize>(
     mut client: TcpStream,
     state: GlobalState,
     handlers: [SharedCallable; N],
+    mut outgoing: broadcast::Receiver<Line>,
 ) {
     let (read, mut write) = client.split();
 
     let tags = twitch::Tags::parse(&mut "@badges=broadcaster/1 ").unwrap();
-    let (user, channel) = (Arc::from("museun"), Arc::from("#museun"));
+    let (user, channel) = (Arc::from("museun"), Arc::from(CHANNEL));
 
     let mut reader = BufReader::new(read).lines();
-    while let Ok(Some(line)) = reader.next_line().await {
+    loop {
+        let line = tokio::select! {
+            line = reader.next_line() => match line {
+                Ok(Some(line)) => line,
+                _ => break,
+            },
+            Ok(line) = outgoing.recv() => {
+                let _ = send_message(&mut write, line).await;
+                continue;
+            }
+        };
+
         let msg = Message::new(
             twitch::Message::from_pm(twitch::Privmsg {
                 tags: tags.clone(),
@@ -60,25 +108,42 @@ async fn handle<const N: usize>(
             state.clone(),
         );
 
-        for resp in dispatch_and_render(&handlers, &msg, RenderFlavor::Twitch).await {
-            let (kind, out) = match resp {
-                Response::Say(msg) => ("say", msg),
-                Response::Reply(msg) => ("reply", msg),
-                Response::Problem(msg) => ("problem", msg),
-            };
-            let out = format!("{kind} -> {out}");
-            let _ = send_message(&mut write, Line::Message { data: &out }).await;
+        for resp in dispatch_and_render(&handlers, &msg, RenderFlavor::Plain).await {
+            let _ = send_message(&mut write, Line::from(resp)).await;
         }
         let _ = send_message(&mut write, Line::None).await;
     }
 }
 
-#[derive(serde::Serialize)]
-enum Line<'a> {
-    Message { data: &'a str },
+#[derive(Clone, serde::Serialize)]
+enum Line {
+    Message { data: String },
+    Action { data: String },
+    Private { data: String },
+    Embed { embed: Embed },
+    React { emoji: String },
+    Delete,
     None,
 }
 
+impl From<Response> for Line {
+    fn from(resp: Response) -> Self {
+        let (kind, data) = match resp {
+            Response::Say(data) => ("say", data),
+            Response::Reply(data) => ("reply", data),
+            Response::Problem(data) => ("problem", data),
+            Response::Action(data) => return Self::Action { data },
+            Response::Private(data) => return Self::Private { data },
+            Response::Embed(embed) => return Self::Embed { embed },
+            Response::React(emoji) => return Self::React { emoji },
+            Response::Delete => return Self::Delete,
+        };
+        Self::Message {
+            data: format!("{kind} -> {data}"),
+        }
+    }
+}
+
 async fn send_message(
     mut w: impl AsyncWrite + Send + Unpin + Sized,
     msg: impl serde::Serialize + Send + Sync,
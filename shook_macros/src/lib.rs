@@ -0,0 +1,306 @@
+use std::collections::HashSet;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Ident, ImplItemMethod, LitStr, Token,
+};
+
+// #[command(name = "!crates", usage = "<name> --exact", help = "...", aliases = ["!crate"], permission = "moderator")]
+//
+// this goes on a handler method. the method is kept as is, and `<method>_command()` is added next to it
+// which returns the `Command`, so it can be bound with `bind_cmd(Self::crates_command(), Self::crates)`
+#[proc_macro_attribute]
+pub fn command(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as Args);
+    let method = parse_macro_input!(item as ImplItemMethod);
+    expand(args, method)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+fn expand(args: Args, method: ImplItemMethod) -> syn::Result<TokenStream2> {
+    let Args {
+        name,
+        usage,
+        help,
+        aliases,
+        permission,
+    } = args;
+
+    let vis = &method.vis;
+    let ident = format_ident!("{}_command", method.sig.ident);
+
+    let help = help.map(|help| quote!(.help(#help)));
+    let permission = match permission {
+        Some(permission) => {
+            let variant = permission_variant(&permission)?;
+            Some(quote!(.permission(::shook_core::callable::Permission::#variant)))
+        }
+        None => None,
+    };
+    // this is checked here, but the parser in `shook_core` has the final say
+    let usage = match usage {
+        Some(usage) => {
+            check_usage(&usage)?;
+            let msg = format!("invalid usage for {}", name.value());
+            Some(quote!(.usage(#usage).expect(#msg)))
+        }
+        None => None,
+    };
+
+    Ok(quote! {
+        #method
+
+        #vis fn #ident() -> ::shook_core::callable::Command {
+            ::shook_core::callable::Command::new(#name)
+                #help
+                #(.alias(#aliases))*
+                #permission
+                #usage
+        }
+    })
+}
+
+struct Args {
+    name: LitStr,
+    usage: Option<LitStr>,
+    help: Option<LitStr>,
+    aliases: Vec<LitStr>,
+    permission: Option<LitStr>,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let list = Punctuated::<Arg, Token![,]>::parse_terminated(input)?;
+
+        let mut seen = HashSet::new();
+        let (mut name, mut usage, mut help, mut aliases, mut permission) =
+            (None, None, None, Vec::new(), None);
+
+        for arg in list {
+            let key = arg.key.to_string();
+            if !seen.insert(key.clone()) {
+                return Err(syn::Error::new(
+                    arg.key.span(),
+                    format!("'{key}' was repeated"),
+                ));
+            }
+
+            match &*key {
+                "name" => name = Some(command_name(arg.string()?)?),
+                "usage" => usage = Some(arg.string()?),
+                "help" => help = Some(arg.string()?),
+                "aliases" => {
+                    aliases = arg
+                        .list()?
+                        .into_iter()
+                        .map(command_name)
+                        .collect::<syn::Result<_>>()?
+                }
+                "permission" => permission = Some(arg.string()?),
+                _ => {
+                    let msg = format!(
+                        "unknown key '{key}', expected one of: name, usage, help, aliases, permission"
+                    );
+                    return Err(syn::Error::new(arg.key.span(), msg));
+                }
+            }
+        }
+
+        let name = name.ok_or_else(|| {
+            syn::Error::new(
+                Span::call_site(),
+                r#"a command needs a name, e.g. `name = "!hello"`"#,
+            )
+        })?;
+
+        Ok(Self {
+            name,
+            usage,
+            help,
+            aliases,
+            permission,
+        })
+    }
+}
+
+struct Arg {
+    key: Ident,
+    value: Value,
+}
+
+enum Value {
+    String(LitStr),
+    List(Vec<LitStr>),
+}
+
+impl Arg {
+    fn string(self) -> syn::Result<LitStr> {
+        match self.value {
+            Value::String(s) => Ok(s),
+            Value::List(..) => Err(syn::Error::new(
+                self.key.span(),
+                format!("'{}' should be a string", self.key),
+            )),
+        }
+    }
+
+    fn list(self) -> syn::Result<Vec<LitStr>> {
+        match self.value {
+            Value::List(list) => Ok(list),
+            Value::String(s) => Err(syn::Error::new(
+                s.span(),
+                format!("'{}' should be a list, e.g. [\"!a\", \"!b\"]", self.key),
+            )),
+        }
+    }
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = input.parse()?;
+        input.parse::<Token![=]>()?;
+
+        let value = if input.peek(syn::token::Bracket) {
+            let content;
+            syn::bracketed!(content in input);
+            let list = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+            Value::List(list.into_iter().collect())
+        } else {
+            Value::String(input.parse()?)
+        };
+
+        Ok(Self { key, value })
+    }
+}
+
+fn command_name(name: LitStr) -> syn::Result<LitStr> {
+    let value = name.value();
+    if value.is_empty() || value.contains(char::is_whitespace) {
+        return Err(syn::Error::new(
+            name.span(),
+            "a command name cannot be empty or have spaces",
+        ));
+    }
+    Ok(name)
+}
+
+// <required> <optional?> <rest..> <key:type> <key:type?> --switch --key=type
+// where a type is a number (u8 to i64, usize or isize), string, @user, duration or a choice like spotify|youtube.
+// this has the same rules as `ExampleArgs::parse`, so `.usage(..)` cannot fail on what this accepts
+fn check_usage(usage: &LitStr) -> syn::Result<()> {
+    let error = |msg: String| syn::Error::new(usage.span(), msg);
+
+    let value = usage.value();
+    let mut seen = Vec::new();
+    let mut optional = None;
+    let mut variadic = None;
+
+    for token in value.split_ascii_whitespace() {
+        let (key, ty) = match token.strip_prefix("--") {
+            Some(flag) => match flag.split_once('=') {
+                Some((key, ty)) => (key, Some(ty)),
+                None => (flag, None),
+            },
+            None => {
+                if let Some(prev) = variadic {
+                    return Err(error(format!(
+                        "'{token}' cannot come after the variadic <{prev}..>"
+                    )));
+                }
+
+                let inner = token
+                    .strip_prefix('<')
+                    .and_then(|s| s.strip_suffix('>'))
+                    .ok_or_else(|| {
+                        error(format!(
+                            "invalid pattern: '{token}', expected <arg> or --flag"
+                        ))
+                    })?;
+
+                let (inner, is_variadic, is_optional) =
+                    if let Some(inner) = inner.strip_suffix("..") {
+                        (inner, true, false)
+                    } else if let Some(inner) = inner.strip_suffix('?') {
+                        (inner, false, true)
+                    } else {
+                        (inner, false, false)
+                    };
+                let (key, ty) = match inner.split_once(':') {
+                    Some((key, ty)) => (key, Some(ty)),
+                    None => (inner, None),
+                };
+
+                if is_variadic {
+                    variadic = Some(key);
+                } else if is_optional {
+                    optional.get_or_insert(key);
+                } else if let Some(prev) = optional {
+                    return Err(error(format!(
+                        "required <{key}> cannot come after optional <{prev}?>"
+                    )));
+                }
+                (key, ty)
+            }
+        };
+
+        let valid = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+        if key.is_empty() || !key.chars().all(valid) {
+            return Err(error(format!(
+                "invalid key in '{token}', a key can only have letters, numbers, _ and -"
+            )));
+        }
+        if seen.contains(&key) {
+            return Err(error(format!("{key} was already used (in '{token}')")));
+        }
+        seen.push(key);
+
+        if let Some(ty) = ty {
+            check_type(ty).map_err(|msg| error(format!("{msg} (in '{token}')")))?;
+        }
+    }
+    Ok(())
+}
+
+fn check_type(ty: &str) -> Result<(), String> {
+    const TYPES: &[&str] = &[
+        "string", "@user", "duration", "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32",
+        "i64", "isize",
+    ];
+
+    if TYPES.contains(&ty) {
+        return Ok(());
+    }
+    if ty.contains('|') {
+        if ty.split('|').any(str::is_empty) {
+            return Err(String::from("an empty choice"));
+        }
+        return Ok(());
+    }
+    Err(format!(
+        "unknown type '{ty}', expected a number (e.g. u32), string, @user, duration or a choice like a|b"
+    ))
+}
+
+fn permission_variant(permission: &LitStr) -> syn::Result<Ident> {
+    let variant = match &*permission.value() {
+        "everyone" => "Everyone",
+        "subscriber" => "Subscriber",
+        "vip" => "Vip",
+        "moderator" => "Moderator",
+        "broadcaster" => "Broadcaster",
+        "bot-owner" => "BotOwner",
+        other => {
+            let msg = format!(
+                "unknown permission '{other}', expected one of: everyone, subscriber, vip, moderator, broadcaster, bot-owner"
+            );
+            return Err(syn::Error::new(permission.span(), msg));
+        }
+    };
+    Ok(Ident::new(variant, permission.span()))
+}
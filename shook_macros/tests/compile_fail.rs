@@ -0,0 +1,5 @@
+// each file in `ui` has to fail with the error next to it. run with TRYBUILD=overwrite to update them
+#[test]
+fn compile_fail() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
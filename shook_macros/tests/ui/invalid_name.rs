@@ -0,0 +1,13 @@
+use shook_macros::command;
+
+struct Handlers;
+
+impl Handlers {
+    #[command(name = "!hello there")]
+    async fn hello(&self) {}
+
+    #[command(name = "!hi", aliases = ["!hey", ""])]
+    async fn hi(&self) {}
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use shook_macros::command;
+
+struct Handlers;
+
+impl Handlers {
+    #[command(name = "!hello", permission = "admin")]
+    async fn hello(&self) {}
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+use shook_macros::command;
+
+struct Handlers;
+
+impl Handlers {
+    #[command(name = "!pattern", usage = "name")]
+    async fn pattern(&self) {}
+
+    #[command(name = "!key", usage = "<first.name>")]
+    async fn key(&self) {}
+
+    #[command(name = "!flag", usage = "--=u32")]
+    async fn flag(&self) {}
+
+    #[command(name = "!number", usage = "<count:u33>")]
+    async fn number(&self) {}
+
+    #[command(name = "!choice", usage = "<mode:spotify||youtube>")]
+    async fn choice(&self) {}
+
+    #[command(name = "!twice", usage = "<name> --name")]
+    async fn twice(&self) {}
+
+    #[command(name = "!order", usage = "<first?> <second>")]
+    async fn order(&self) {}
+
+    #[command(name = "!rest", usage = "<body..> <signature>")]
+    async fn rest(&self) {}
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use shook_macros::command;
+
+struct Handlers;
+
+impl Handlers {
+    #[command(name = "!hello", aliases = "!hi")]
+    async fn hello(&self) {}
+
+    #[command(name = ["!hi"])]
+    async fn hi(&self) {}
+}
+
+fn main() {}
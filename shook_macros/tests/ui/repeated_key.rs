@@ -0,0 +1,10 @@
+use shook_macros::command;
+
+struct Handlers;
+
+impl Handlers {
+    #[command(name = "!hello", help = "says hello", help = "says hi")]
+    async fn hello(&self) {}
+}
+
+fn main() {}
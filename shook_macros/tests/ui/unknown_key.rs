@@ -0,0 +1,10 @@
+use shook_macros::command;
+
+struct Handlers;
+
+impl Handlers {
+    #[command(name = "!hello", halp = "says hello")]
+    async fn hello(&self) {}
+}
+
+fn main() {}
@@ -1,6 +1,12 @@
 use shook_config::Ephemeral;
+use shook_core::prelude::Prefix;
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub struct Config {
     pub oauth_token: Ephemeral,
+    #[serde(default)]
+    pub prefix: Prefix,
+    // scheduled output is sent to these channel ids
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub announce: Vec<u64>,
 }
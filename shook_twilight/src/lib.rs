@@ -5,17 +5,26 @@ use anyhow::Context;
 use tokio_stream::StreamExt as _;
 use twilight_gateway::{Intents, Shard};
 
-use twilight_http::{request::channel::message::create_message::CreateMessage, Client};
+use twilight_http::{
+    request::channel::{message::create_message::CreateMessage, reaction::RequestReactionType},
+    Client,
+};
 use twilight_model::{
+    channel::embed::{Embed, EmbedField},
     channel::message::MessageType,
     channel::Message,
-    id::{marker::ChannelMarker, Id},
+    id::{
+        marker::{ChannelMarker, UserMarker},
+        Id,
+    },
 };
 
 use shook_core::{
     callable::SharedCallable,
-    prelude::GlobalState,
+    outbox::{Outbox, Sender, Transport},
+    prelude::{GlobalState, Prefix, Response},
     render::{dispatch_and_render, RenderFlavor},
+    schedule,
 };
 
 use crate::message::TwilightMessage;
@@ -28,7 +37,7 @@ pub async fn create_bot<const N: usize>(
     state: GlobalState,
     handlers: [SharedCallable; N],
 ) -> anyhow::Result<()> {
-    let config: crate::config::Config = state.get_owned().await;
+    let config: crate::config::Config = state.get_owned()?;
 
     let client = Arc::new(twilight_http::Client::new(config.oauth_token.into_string()));
 
@@ -38,10 +47,28 @@ pub async fn create_bot<const N: usize>(
     );
     shard.start().await?;
 
+    let outbox = state.get_or_default::<Outbox>();
+    outbox.register(
+        Transport::Discord,
+        DiscordSender {
+            client: client.clone(),
+        },
+    );
+
+    if !config.announce.is_empty() {
+        let channels = config.announce.iter().map(ToString::to_string).collect();
+        tokio::spawn(schedule::deliver(
+            state.clone(),
+            Transport::Discord,
+            channels,
+        ));
+    }
+
     let bot = Bot {
         state,
         handlers,
         client: client.clone(),
+        prefix: config.prefix.clone(),
     };
 
     let seen = state::DiscordState::default();
@@ -66,7 +93,10 @@ pub async fn create_bot<const N: usize>(
                     .await?;
 
                 log::debug!("[{}] {}: {}", channel, msg.author.name, msg.content);
-                bot.handle(msg.0).await?;
+                // one bad response shouldn't disconnect the bot
+                if let Err(err) = bot.handle(msg.0, our_user_id).await {
+                    log::warn!("cannot respond in {channel}: {err:#}")
+                }
             }
             twilight_gateway::Event::Ready(msg) => {
                 log::debug!("discord bot name: {}, id: {}", msg.user.name, msg.user.id);
@@ -83,16 +113,28 @@ struct Bot<const N: usize> {
     state: GlobalState,
     handlers: [SharedCallable; N],
     client: Arc<Client>,
+    prefix: Prefix,
 }
 
 impl<const N: usize> Bot<N> {
-    async fn handle(&self, msg: Message) -> anyhow::Result<()> {
-        use shook_core::prelude::{Message as ShookMessage, Response};
+    async fn handle(
+        &self,
+        msg: Message,
+        our_user_id: Option<Id<UserMarker>>,
+    ) -> anyhow::Result<()> {
+        use shook_core::prelude::Message as ShookMessage;
 
-        let (ch, id) = (msg.channel_id, msg.id);
+        let (ch, id, author) = (msg.channel_id, msg.id, msg.author.id);
         let source = get_channel_name(&self.client, ch).await?;
 
-        let msg = ShookMessage::new(TwilightMessage { inner: msg, source }, self.state.clone());
+        // discord spells a mention of the bot either way, depending on the client
+        let mentions = our_user_id
+            .map(|id| vec![format!("<@{id}>"), format!("<@!{id}>")])
+            .unwrap_or_default();
+        let prefixes = self.prefix.resolve(&source, &mentions);
+
+        let msg = ShookMessage::new(TwilightMessage { inner: msg, source }, self.state.clone())
+            .with_prefixes(prefixes);
         for resp in dispatch_and_render(&self.handlers, &msg, RenderFlavor::Discord).await {
             match resp {
                 Response::Say(resp) => {
@@ -105,12 +147,43 @@ impl<const N: usize> Bot<N> {
                     let resp = format!("I ran into a problem: {resp}");
                     self.create_message(ch, &resp, |msg| msg.reply(id)).await?;
                 }
+                // discord doesn't have actions, so this is close enough
+                Response::Action(resp) => {
+                    let resp = format!("*{resp}*");
+                    self.create_message(ch, &resp, |msg| msg).await?;
+                }
+                Response::Private(resp) => match self.private_channel(author).await {
+                    Ok(dm) => self.create_message(dm, &resp, |msg| msg).await?,
+                    // this shouldn't stop the bot, and the response isn't meant for the channel
+                    Err(err) => log::warn!("cannot send a private message to {author}: {err}"),
+                },
+                Response::Embed(embed) => {
+                    let embeds = [to_embed(embed)];
+                    match self.client.create_message(ch).embeds(&embeds) {
+                        Ok(msg) => {
+                            let _ = msg.exec().await;
+                        }
+                        Err(err) => log::warn!("cannot send an embed to {ch}: {err}"),
+                    }
+                }
+                Response::React(emoji) => {
+                    let emoji = to_reaction(&emoji);
+                    let _ = self.client.create_reaction(ch, id, &emoji).exec().await;
+                }
+                Response::Delete => {
+                    let _ = self.client.delete_message(ch, id).exec().await;
+                }
             }
         }
 
         Ok(())
     }
 
+    async fn private_channel(&self, user: Id<UserMarker>) -> anyhow::Result<Id<ChannelMarker>> {
+        let dm = self.client.create_private_channel(user).exec().await?;
+        Ok(dm.model().await?.id)
+    }
+
     async fn create_message<'r>(
         &'r self,
         ch: Id<ChannelMarker>,
@@ -123,6 +196,119 @@ impl<const N: usize> Bot<N> {
     }
 }
 
+// the channel is addressed by its id
+struct DiscordSender {
+    client: Arc<Client>,
+}
+
+#[async_trait::async_trait]
+impl Sender for DiscordSender {
+    async fn send(&self, channel: &str, responses: Vec<Response>) -> anyhow::Result<()> {
+        let ch = channel
+            .parse()
+            .ok()
+            .and_then(Id::new_checked)
+            .with_context(|| format!("invalid channel id: {channel}"))?;
+
+        for resp in responses {
+            let data = match resp {
+                Response::Say(data) | Response::Reply(data) => data,
+                Response::Problem(data) => format!("I ran into a problem: {data}"),
+                Response::Action(data) => format!("*{data}*"),
+                Response::Embed(embed) => {
+                    let embeds = [to_embed(embed)];
+                    self.client
+                        .create_message(ch)
+                        .embeds(&embeds)?
+                        .exec()
+                        .await?;
+                    continue;
+                }
+                // these need a message to respond to
+                resp @ (Response::Private(..) | Response::React(..) | Response::Delete) => {
+                    log::warn!("cannot send {resp:?} to {channel} without a message");
+                    continue;
+                }
+            };
+            self.client
+                .create_message(ch)
+                .content(&data)?
+                .exec()
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+// discord rejects an embed over any of these, so the text is cut to fit
+const TITLE_LEN: usize = 256;
+const FIELDS: usize = 25;
+const FIELD_NAME_LEN: usize = 256;
+const FIELD_VALUE_LEN: usize = 1024;
+const EMBED_LEN: usize = 6000;
+
+// fields which don't fit in what is left of `EMBED_LEN` are left out
+fn to_embed(embed: shook_core::render::Embed) -> Embed {
+    let title = truncate(embed.title, TITLE_LEN);
+    let mut left = EMBED_LEN.saturating_sub(title.chars().count());
+    let fields = embed
+        .fields
+        .into_iter()
+        .take(FIELDS)
+        .map_while(|field| {
+            let name = truncate(field.name, FIELD_NAME_LEN);
+            let value = truncate(field.value, FIELD_VALUE_LEN);
+            left = left.checked_sub(name.chars().count() + value.chars().count())?;
+            Some(EmbedField {
+                inline: field.inline,
+                name,
+                value,
+            })
+        })
+        .collect();
+
+    Embed {
+        author: None,
+        color: embed.color,
+        description: None,
+        fields,
+        footer: None,
+        image: None,
+        kind: String::from("rich"),
+        provider: None,
+        thumbnail: None,
+        timestamp: None,
+        title: Some(title),
+        url: embed.url,
+        video: None,
+    }
+}
+
+// this cuts `text` to `max` characters. discord needs some text in a field, so an empty one gets a zero width space
+fn truncate(text: String, max: usize) -> String {
+    if text.is_empty() {
+        return String::from("\u{200b}");
+    }
+    if text.chars().count() <= max {
+        return text;
+    }
+    text.chars().take(max - 1).chain(['…']).collect()
+}
+
+// custom emojis are written as `name:id`
+fn to_reaction(emoji: &str) -> RequestReactionType<'_> {
+    match emoji
+        .rsplit_once(':')
+        .and_then(|(name, id)| Some((name, Id::new_checked(id.parse().ok()?)?)))
+    {
+        Some((name, id)) => RequestReactionType::Custom {
+            id,
+            name: Some(name),
+        },
+        None => RequestReactionType::Unicode { name: emoji },
+    }
+}
+
 async fn get_channel_name(client: &Client, id: Id<ChannelMarker>) -> anyhow::Result<String> {
     let resp = client.channel(id).exec().await?;
     let name = resp
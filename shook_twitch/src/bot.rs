@@ -1,27 +1,35 @@
 use shook_core::{
-    prelude::{GlobalState, Message, RenderFlavor, Response, SharedCallable},
+    outbox::Sender,
+    prelude::{GlobalState, Message, Prefix, RenderFlavor, Response, SharedCallable},
     render::dispatch_and_render,
 };
 
-use super::{Connection, Message as TwitchMessage, Privmsg};
+use super::{connection::Writer, Connection, Message as TwitchMessage, Privmsg};
 
 pub struct Bot<const N: usize> {
     conn: Connection,
     state: GlobalState,
     callables: [SharedCallable; N],
+    prefix: Prefix,
 }
 
 impl<const N: usize> Bot<N> {
-    pub const fn new(conn: Connection, state: GlobalState, callables: [SharedCallable; N]) -> Self {
+    pub const fn new(
+        conn: Connection,
+        state: GlobalState,
+        callables: [SharedCallable; N],
+        prefix: Prefix,
+    ) -> Self {
         Self {
             conn,
             state,
             callables,
+            prefix,
         }
     }
 
-    pub async fn join(&mut self, channel: &str) -> anyhow::Result<()> {
-        self.conn.write_raw(&format!("JOIN {channel}\r\n")).await
+    pub fn join(&self, channel: &str) -> anyhow::Result<()> {
+        self.conn.write_raw(&format!("JOIN {channel}\r\n"))
     }
 
     pub async fn start(&mut self) -> anyhow::Result<()> {
@@ -34,7 +42,10 @@ impl<const N: usize> Bot<N> {
     async fn dispatch(&mut self, msg: Privmsg) -> anyhow::Result<()> {
         log::debug!("[{}] {}: {}", msg.target, msg.user, msg.data);
 
-        let msg = Message::new(TwitchMessage::from_pm(msg), self.state.clone());
+        let id = msg.tags.get("id").ok().map(ToString::to_string);
+        let prefixes = self.prefix.resolve(&msg.target, &[]);
+        let msg =
+            Message::new(TwitchMessage::from_pm(msg), self.state.clone()).with_prefixes(prefixes);
 
         let sender = msg.sender_name();
         let channel = msg.source();
@@ -50,10 +61,68 @@ impl<const N: usize> Bot<N> {
                 Response::Problem(msg) => {
                     format!("PRIVMSG {channel} :a problem occurred: {msg}\r\n")
                 }
+                Response::Action(msg) => {
+                    format!("PRIVMSG {channel} :\x01ACTION {msg}\x01\r\n")
+                }
+                Response::Private(msg) => {
+                    format!("PRIVMSG {channel} :/w {sender} {msg}\r\n")
+                }
+                Response::Embed(embed) => {
+                    format!("PRIVMSG {channel} :{}\r\n", one_line(&embed.to_text()))
+                }
+                // twitch doesn't have reactions
+                Response::React(emoji) => {
+                    format!("PRIVMSG {channel} :{sender}: {emoji}\r\n")
+                }
+                Response::Delete => match &id {
+                    Some(id) => format!("PRIVMSG {channel} :/delete {id}\r\n"),
+                    None => continue,
+                },
             };
-            self.conn.write_raw(&out).await?
+            self.conn.write_raw(&out)?
         }
 
         Ok(())
     }
 }
+
+#[async_trait::async_trait]
+impl Sender for Writer {
+    async fn send(&self, channel: &str, responses: Vec<Response>) -> anyhow::Result<()> {
+        let channel = if channel.starts_with('#') {
+            channel.to_string()
+        } else {
+            format!("#{channel}")
+        };
+
+        for resp in responses {
+            let out = match resp {
+                // there is no one to reply to
+                Response::Say(msg) | Response::Reply(msg) => {
+                    format!("PRIVMSG {channel} :{msg}\r\n")
+                }
+                Response::Problem(msg) => {
+                    format!("PRIVMSG {channel} :a problem occurred: {msg}\r\n")
+                }
+                Response::Action(msg) => {
+                    format!("PRIVMSG {channel} :\x01ACTION {msg}\x01\r\n")
+                }
+                Response::Embed(embed) => {
+                    format!("PRIVMSG {channel} :{}\r\n", one_line(&embed.to_text()))
+                }
+                // these need a message to respond to
+                resp @ (Response::Private(..) | Response::React(..) | Response::Delete) => {
+                    log::warn!("cannot send {resp:?} to {channel} without a message");
+                    continue;
+                }
+            };
+            self.write_raw(&out)?
+        }
+        Ok(())
+    }
+}
+
+// a newline would end the PRIVMSG early, and the rest would be sent as its own command
+fn one_line(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
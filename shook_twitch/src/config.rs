@@ -1,4 +1,5 @@
 use shook_config::Ephemeral;
+use shook_core::prelude::Prefix;
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub struct Config {
@@ -6,4 +7,9 @@ pub struct Config {
     pub name: String,
     pub password: Ephemeral,
     pub channel: String,
+    #[serde(default)]
+    pub prefix: Prefix,
+    // scheduled output is sent to these channels
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub announce: Vec<String>,
 }
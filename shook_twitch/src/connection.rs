@@ -2,17 +2,58 @@ use std::{sync::Arc, time::Duration};
 
 use anyhow::Context;
 use tokio::{
-    io::{AsyncBufReadExt, AsyncWriteExt, BufStream},
-    net::TcpStream,
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpStream,
+    },
+    sync::mpsc,
 };
 
 use super::{parser, tags::Tags, types::Registration, Identity, Privmsg};
 
 pub struct Connection {
-    stream: BufStream<TcpStream>,
+    reader: BufReader<OwnedReadHalf>,
+    writer: Writer,
     buf: String,
 }
 
+// this can be cloned, so other tasks can write to the connection while it is being read
+#[derive(Clone)]
+pub struct Writer {
+    tx: mpsc::UnboundedSender<String>,
+}
+
+impl Writer {
+    pub fn write_raw(&self, data: &str) -> anyhow::Result<()> {
+        log::trace!("-> {}", data.escape_debug());
+        let mut data = data.to_string();
+        if !data.ends_with('\n') {
+            data.push_str("\r\n");
+        }
+        self.tx
+            .send(data)
+            .map_err(|_| anyhow::anyhow!("connection was closed"))
+    }
+
+    fn spawn(mut stream: BufWriter<impl AsyncWriteExt + Unpin + Send + 'static>) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        tokio::spawn(async move {
+            while let Some(data) = rx.recv().await {
+                let result = async {
+                    stream.write_all(data.as_bytes()).await?;
+                    stream.flush().await
+                };
+                if let Err(err) = result.await {
+                    log::error!("cannot write to twitch: {err}");
+                    break;
+                }
+            }
+        });
+        Self { tx }
+    }
+}
+
 impl Connection {
     pub async fn connect(addr: &str, reg: Registration<'_>) -> anyhow::Result<(Identity, Self)> {
         async fn try_connect(attempts: usize, addr: &str) -> anyhow::Result<TcpStream> {
@@ -54,22 +95,26 @@ impl Connection {
         }
         stream.flush().await?;
 
-        let mut stream = BufStream::new(stream);
+        let (read, mut write) = stream.into_split();
+        let mut reader = BufReader::new(read);
         let mut buf = String::with_capacity(1024);
-        let identity = Self::wait_for_ready(name, &mut buf, &mut stream).await?;
+        let identity = Self::wait_for_ready(name, &mut buf, &mut reader, &mut write).await?;
         buf.clear();
 
-        Ok((identity, Self { stream, buf }))
+        let this = Self {
+            reader,
+            writer: Writer::spawn(BufWriter::new(write)),
+            buf,
+        };
+        Ok((identity, this))
     }
 
-    pub async fn write_raw(&mut self, data: &str) -> anyhow::Result<()> {
-        log::trace!("-> {}", data.escape_debug());
-        self.stream.write_all(data.as_bytes()).await?;
-        if !data.ends_with('\n') {
-            self.stream.write_all(b"\r\n").await?;
-        }
-        self.stream.flush().await?;
-        Ok(())
+    pub fn writer(&self) -> Writer {
+        self.writer.clone()
+    }
+
+    pub fn write_raw(&self, data: &str) -> anyhow::Result<()> {
+        self.writer.write_raw(data)
     }
 
     pub async fn read_privmsg(&mut self) -> anyhow::Result<Privmsg> {
@@ -77,7 +122,7 @@ impl Connection {
         loop {
             self.buf.clear();
 
-            let n = self.stream.read_line(&mut self.buf).await?;
+            let n = self.reader.read_line(&mut self.buf).await?;
             let line = &self.buf[..n];
             log::trace!("<- {}", line.escape_debug());
 
@@ -87,9 +132,7 @@ impl Connection {
 
             match cmd {
                 "PING" => {
-                    let resp = format!("PONG :{}\r\n", data.unwrap());
-                    self.stream.write_all(resp.as_bytes()).await?;
-                    self.stream.flush().await?;
+                    self.write_raw(&format!("PONG :{}\r\n", data.unwrap()))?;
                 }
                 "ERROR" => anyhow::bail!("error: {:?}", data),
                 "PRIVMSG" => {
@@ -108,10 +151,11 @@ impl Connection {
     async fn wait_for_ready(
         default_name: &str,
         buf: &mut String,
-        stream: &mut BufStream<TcpStream>,
+        reader: &mut BufReader<OwnedReadHalf>,
+        writer: &mut OwnedWriteHalf,
     ) -> anyhow::Result<Identity> {
         loop {
-            let n = stream.read_line(buf).await?;
+            let n = reader.read_line(buf).await?;
             if n == 0 {
                 anyhow::bail!("unexpected eof")
             }
@@ -132,7 +176,7 @@ impl Connection {
                         .next()
                         .with_context(|| "PING must have a token")?;
                     let out = format!("PONG :{token}\r\n");
-                    stream.write_all(out.as_bytes()).await?;
+                    writer.write_all(out.as_bytes()).await?;
                 }
                 Some((.., "GLOBALUSERSTATE")) => {
                     let name = tags.get("display-name").unwrap_or(default_name).into();
@@ -1,4 +1,8 @@
-use shook_core::prelude::{GlobalState, SharedCallable};
+use shook_core::{
+    outbox::{Outbox, Transport},
+    prelude::{GlobalState, SharedCallable},
+    schedule,
+};
 
 pub mod config;
 
@@ -18,7 +22,7 @@ pub async fn create_bot<const N: usize>(
     state: GlobalState,
     callables: [SharedCallable; N],
 ) -> anyhow::Result<()> {
-    let config: crate::config::Config = state.get_owned().await;
+    let config: crate::config::Config = state.get_owned()?;
 
     let reg = types::Registration {
         name: &config.name,
@@ -31,13 +35,21 @@ pub async fn create_bot<const N: usize>(
         &config.name
     );
     let (identity, conn) = Connection::connect(&config.address, reg).await?;
-    state.insert(identity).await;
+    state.insert(identity);
 
     log::info!("connected");
 
-    let mut bot = bot::Bot::new(conn, state, callables);
+    let outbox = state.get_or_default::<Outbox>();
+    outbox.register(Transport::Twitch, conn.writer());
+
+    if !config.announce.is_empty() {
+        let announce = schedule::deliver(state.clone(), Transport::Twitch, config.announce.clone());
+        tokio::spawn(announce);
+    }
+
+    let mut bot = bot::Bot::new(conn, state, callables, config.prefix.clone());
     log::info!("joining {}", &config.channel);
-    bot.join(&config.channel).await?;
+    bot.join(&config.channel)?;
 
     log::info!("starting the twitch bot");
     bot.start().await?;
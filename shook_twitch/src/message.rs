@@ -62,6 +62,16 @@ impl MessageType for Message {
             .any(|(key, val)| key == "moderator" && val == "1")
     }
 
+    fn is_from_vip(&self) -> bool {
+        self.badge_iter()
+            .any(|(key, val)| key == "vip" && val == "1")
+    }
+
+    fn is_from_subscriber(&self) -> bool {
+        self.badge_iter()
+            .any(|(key, _)| matches!(key, "subscriber" | "founder"))
+    }
+
     fn is_from_twitch(&self) -> bool {
         true
     }